@@ -0,0 +1,132 @@
+"""Writes pot_2.ptau, a powers of tau file over BN254 with 2^2 powers and a known tau.
+
+The layout is that of snarkjs `powersoftau new` followed by a contribution: the header,
+the powers of tau in G1 and G2, alpha and beta times the powers of tau in G1, beta in G2
+and the contributions, all points in little-endian Montgomery form. The secrets are known,
+so the file is only fit for tests.
+"""
+
+import struct
+import sys
+
+Q = 21888242871839275222246405745257275088696311157297823662689037894645226208583
+R = 21888242871839275222246405745257275088548364400416034343698204186575808495617
+MONT = pow(2, 256, Q)
+
+POWER = 2
+TAU = 1234567
+ALPHA = 89
+BETA = 97
+
+
+def inv(x):
+    return pow(x, Q - 2, Q)
+
+
+# Fq2 = Fq[u] / (u^2 + 1), elements as (c0, c1)
+def f2_add(a, b):
+    return ((a[0] + b[0]) % Q, (a[1] + b[1]) % Q)
+
+
+def f2_sub(a, b):
+    return ((a[0] - b[0]) % Q, (a[1] - b[1]) % Q)
+
+
+def f2_mul(a, b):
+    return ((a[0] * b[0] - a[1] * b[1]) % Q, (a[0] * b[1] + a[1] * b[0]) % Q)
+
+
+def f2_inv(a):
+    d = inv((a[0] * a[0] + a[1] * a[1]) % Q)
+    return (a[0] * d % Q, -a[1] * d % Q)
+
+
+FQ = (
+    lambda a, b: (a + b) % Q,
+    lambda a, b: (a - b) % Q,
+    lambda a, b: a * b % Q,
+    inv,
+    lambda x: x * 3,
+)
+FQ2 = (f2_add, f2_sub, f2_mul, f2_inv, lambda x: (x[0] * 3, x[1] * 3))
+
+
+def add(field, p, q):
+    f_add, f_sub, f_mul, f_inv, f_3 = field
+    if p is None:
+        return q
+    if q is None:
+        return p
+    if p[0] == q[0]:
+        if f_add(p[1], q[1]) == f_sub(p[1], p[1]):
+            return None
+        slope = f_mul(f_3(f_mul(p[0], p[0])), f_inv(f_add(p[1], p[1])))
+    else:
+        slope = f_mul(f_sub(q[1], p[1]), f_inv(f_sub(q[0], p[0])))
+    x = f_sub(f_sub(f_mul(slope, slope), p[0]), q[0])
+    y = f_sub(f_mul(slope, f_sub(p[0], x)), p[1])
+    return (x, y)
+
+
+def mul(field, p, n):
+    acc = None
+    while n:
+        if n & 1:
+            acc = add(field, acc, p)
+        p = add(field, p, p)
+        n >>= 1
+    return acc
+
+
+G1 = (1, 2)
+G2 = (
+    (
+        10857046999023057135944570762232829481370756359578518086990519993285655852781,
+        11559732032986387107991004021392285783925812861821192530917403151452391805634,
+    ),
+    (
+        8495653923123431417604973247489272438418190587263600148770280649306958101930,
+        4082367875863433681332203403145435568316851327593401208105741076214120093531,
+    ),
+)
+
+
+def fq_bytes(x):
+    return (x * MONT % Q).to_bytes(32, "little")
+
+
+def g1_bytes(p):
+    return fq_bytes(p[0]) + fq_bytes(p[1])
+
+
+def g2_bytes(p):
+    return b"".join(fq_bytes(c) for coord in p for c in coord)
+
+
+def powers(x, n):
+    return [pow(x, i, R) for i in range(n)]
+
+
+def section(number, data):
+    return struct.pack("<IQ", number, len(data)) + data
+
+
+def main(file_name):
+    n = 1 << POWER
+    header = struct.pack("<I", 32) + Q.to_bytes(32, "little") + struct.pack("<II", POWER, POWER)
+    tau_g1 = b"".join(g1_bytes(mul(FQ, G1, x)) for x in powers(TAU, 2 * n - 1))
+    tau_g2 = b"".join(g2_bytes(mul(FQ2, G2, x)) for x in powers(TAU, n))
+    alpha_tau_g1 = b"".join(g1_bytes(mul(FQ, G1, ALPHA * x)) for x in powers(TAU, n))
+    beta_tau_g1 = b"".join(g1_bytes(mul(FQ, G1, BETA * x)) for x in powers(TAU, n))
+    beta_g2 = g2_bytes(mul(FQ2, G2, BETA))
+    contributions = struct.pack("<I", 0)
+
+    sections = [header, tau_g1, tau_g2, alpha_tau_g1, beta_tau_g1, beta_g2, contributions]
+    with open(file_name, "wb") as f:
+        f.write(b"ptau" + struct.pack("<II", 1, len(sections)))
+        for number, data in enumerate(sections, 1):
+            f.write(section(number, data))
+
+
+if __name__ == "__main__":
+    main(sys.argv[1] if len(sys.argv) > 1 else "pot_2.ptau")
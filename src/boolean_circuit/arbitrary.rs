@@ -0,0 +1,73 @@
+//! Random circuits for property tests.
+
+use std::ops::RangeInclusive;
+
+use proptest::{prelude::*, sample::Index};
+
+use super::{BooleanCircuit, BooleanCircuitGateIo, BooleanCircuitInstance};
+
+/// Random DAG of the seven gate types.
+///
+/// The primary inputs `in[i]` come first, after up to three unused wires, and each gate
+/// reads two earlier wires and drives a new one. The outputs `out[i]` are the gate outputs
+/// no later gate reads. There must be at least one input.
+pub(crate) fn arb_boolean_circuit(
+    num_inputs: RangeInclusive<usize>,
+    num_gates: RangeInclusive<usize>,
+) -> impl Strategy<Value = BooleanCircuit> {
+    assert!(*num_inputs.start() > 0);
+
+    let gates = prop::collection::vec((1u64..=7, any::<Index>(), any::<Index>()), num_gates);
+
+    (num_inputs, 0u64..4, gates).prop_map(|(num_inputs, wire_offset, gates)| {
+        let inputs = (0..num_inputs as u64)
+            .map(|i| wire_offset + i)
+            .collect::<Vec<_>>();
+
+        let gates = gates
+            .into_iter()
+            .enumerate()
+            .map(|(i, (gate, l, r))| {
+                let num_wires = num_inputs + i;
+                BooleanCircuitGateIo {
+                    gate,
+                    l_idx: wire_offset + l.index(num_wires) as u64,
+                    r_idx: wire_offset + r.index(num_wires) as u64,
+                    o_idx: wire_offset + num_wires as u64,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let outputs = gates
+            .iter()
+            .enumerate()
+            .filter(|(i, gate_io)| {
+                gates[i + 1..]
+                    .iter()
+                    .all(|later| later.l_idx != gate_io.o_idx && later.r_idx != gate_io.o_idx)
+            })
+            .map(|(_, gate_io)| gate_io.o_idx)
+            .collect::<Vec<_>>();
+
+        BooleanCircuit {
+            input_names: (0..inputs.len()).map(|i| format!("in[{}]", i)).collect(),
+            output_names: (0..outputs.len()).map(|i| format!("out[{}]", i)).collect(),
+            max_wire_idx: wire_offset + (num_inputs + gates.len()).max(1) as u64 - 1,
+            inputs,
+            outputs,
+            gates,
+        }
+    })
+}
+
+/// Random circuit from [`arb_boolean_circuit`] evaluated on random inputs.
+pub(crate) fn arb_boolean_circuit_instance(
+    num_inputs: RangeInclusive<usize>,
+    num_gates: RangeInclusive<usize>,
+) -> impl Strategy<Value = BooleanCircuitInstance> {
+    arb_boolean_circuit(num_inputs, num_gates).prop_flat_map(|ckt| {
+        prop::collection::vec(any::<bool>(), ckt.inputs.len()).prop_map(move |inputs| {
+            BooleanCircuitInstance::from_ckt_and_inputs(ckt.clone(), &inputs)
+        })
+    })
+}
@@ -0,0 +1,260 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Write},
+};
+
+use super::BooleanCircuit;
+
+/// A boolean formula in conjunctive normal form.
+///
+/// Variables are numbered from 1 as in DIMACS; wire `i` of the source circuit maps to
+/// variable `i + 1`. A clause is a disjunction of literals, where a negative literal
+/// denotes the negation of its variable.
+#[derive(Clone, Default, Debug)]
+pub struct Cnf {
+    pub num_vars: u64,
+    pub clauses: Vec<Vec<i64>>,
+}
+
+pub(super) fn wire_to_var(wire_idx: u64) -> i64 {
+    (wire_idx + 1) as i64
+}
+
+pub(super) fn var_to_wire(var: i64) -> u64 {
+    var.unsigned_abs() - 1
+}
+
+impl BooleanCircuit {
+    /// Tseitin-encodes the circuit into an equisatisfiable CNF formula.
+    ///
+    /// `fixed` pins the given wires (typically inputs or outputs) to the given values.
+    /// Wires which are neither primary inputs nor driven by a gate are pinned to zero, as
+    /// in [`BooleanCircuit::eval`].
+    ///
+    /// Fails if a fixed wire is not a wire of the circuit.
+    pub fn to_cnf(&self, fixed: &[(u64, bool)]) -> Result<Cnf, Error> {
+        if let Some(&(i, _)) = fixed.iter().find(|&&(i, _)| i > self.max_wire_idx) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "fixed wire {} is not a wire of the circuit, which has {} wires",
+                    i,
+                    self.max_wire_idx + 1
+                ),
+            ));
+        }
+
+        let mut clauses = Vec::new();
+        let mut driven = vec![false; (self.max_wire_idx + 1) as usize];
+
+        for &i in &self.inputs {
+            driven[i as usize] = true;
+        }
+
+        for gate_io in &self.gates {
+            let l = wire_to_var(gate_io.l_idx);
+            let r = wire_to_var(gate_io.r_idx);
+            let o = wire_to_var(gate_io.o_idx);
+
+            match gate_io.gate {
+                // NOT (right input is ignored)
+                1 => {
+                    clauses.push(vec![o, l]);
+                    clauses.push(vec![-o, -l]);
+                }
+                // AND
+                2 => {
+                    clauses.push(vec![-o, l]);
+                    clauses.push(vec![-o, r]);
+                    clauses.push(vec![o, -l, -r]);
+                }
+                // NAND
+                3 => {
+                    clauses.push(vec![o, l]);
+                    clauses.push(vec![o, r]);
+                    clauses.push(vec![-o, -l, -r]);
+                }
+                // OR
+                4 => {
+                    clauses.push(vec![o, -l]);
+                    clauses.push(vec![o, -r]);
+                    clauses.push(vec![-o, l, r]);
+                }
+                // NOR
+                5 => {
+                    clauses.push(vec![-o, -l]);
+                    clauses.push(vec![-o, -r]);
+                    clauses.push(vec![o, l, r]);
+                }
+                // XOR
+                6 => {
+                    clauses.push(vec![-o, l, r]);
+                    clauses.push(vec![-o, -l, -r]);
+                    clauses.push(vec![o, -l, r]);
+                    clauses.push(vec![o, l, -r]);
+                }
+                // XNOR
+                7 => {
+                    clauses.push(vec![o, l, r]);
+                    clauses.push(vec![o, -l, -r]);
+                    clauses.push(vec![-o, -l, r]);
+                    clauses.push(vec![-o, l, -r]);
+                }
+                _ => panic!("invalid gate {}", gate_io.gate),
+            }
+
+            driven[gate_io.o_idx as usize] = true;
+        }
+
+        for (i, is_driven) in driven.iter().enumerate() {
+            if !is_driven {
+                clauses.push(vec![-wire_to_var(i as u64)]);
+            }
+        }
+
+        for &(i, val) in fixed {
+            let var = wire_to_var(i);
+            clauses.push(vec![if val { var } else { -var }]);
+        }
+
+        Ok(Cnf {
+            num_vars: self.max_wire_idx + 1,
+            clauses,
+        })
+    }
+
+    /// Extracts the primary input values from a SAT solver model of [`BooleanCircuit::to_cnf`].
+    ///
+    /// Accepts both the competition format (`s SATISFIABLE` followed by `v ...` lines) and
+    /// the MiniSat result file format (`SAT` followed by a line of literals).
+    pub fn inputs_from_dimacs_model(&self, file_name: &str) -> Result<Vec<bool>, Error> {
+        let file = File::open(file_name)?;
+        let reader = BufReader::new(file);
+
+        let mut wires = vec![false; (self.max_wire_idx + 1) as usize];
+
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('c') {
+                continue;
+            }
+
+            if line.contains("UNSAT") {
+                Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "model file reports the formula as unsatisfiable",
+                ))?;
+            }
+
+            if line.starts_with('s') || line == "SAT" {
+                continue;
+            }
+
+            let literals = line.strip_prefix('v').unwrap_or(line);
+            for lit in literals.split_ascii_whitespace() {
+                let lit: i64 = lit.parse().map_err(|_| {
+                    Error::new(ErrorKind::InvalidData, format!("invalid literal {}", lit))
+                })?;
+                if lit == 0 {
+                    continue;
+                }
+
+                let wire_idx = var_to_wire(lit);
+                if wire_idx <= self.max_wire_idx {
+                    wires[wire_idx as usize] = lit > 0;
+                }
+            }
+        }
+
+        Ok(self.inputs.iter().map(|&i| wires[i as usize]).collect())
+    }
+}
+
+impl Cnf {
+    /// Checks whether the given wire values satisfy every clause.
+    pub fn is_satisfied_by(&self, wires: &[bool]) -> bool {
+        self.clauses.iter().all(|clause| {
+            clause
+                .iter()
+                .any(|&lit| wires[var_to_wire(lit) as usize] == (lit > 0))
+        })
+    }
+
+    pub fn write_dimacs(&self, file_name: &str) -> Result<(), Error> {
+        let file = File::create(file_name)?;
+        let mut writer = BufWriter::new(file);
+
+        writeln!(writer, "c generated by zktsim; variable i is wire i - 1")?;
+        writeln!(writer, "p cnf {} {}", self.num_vars, self.clauses.len())?;
+        for clause in &self.clauses {
+            for lit in clause {
+                write!(writer, "{} ", lit)?;
+            }
+            writeln!(writer, "0")?;
+        }
+
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boolean_circuit::BooleanCircuitGateIo;
+
+    fn all_gates_circuit() -> BooleanCircuit {
+        let mut ckt = BooleanCircuit {
+            inputs: vec![2, 3],
+            ..Default::default()
+        };
+        for gate in 1..=7 {
+            ckt.gates.push(BooleanCircuitGateIo {
+                gate,
+                l_idx: 2,
+                r_idx: 3,
+                o_idx: 3 + gate,
+            });
+            ckt.outputs.push(3 + gate);
+        }
+        ckt.max_wire_idx = 10;
+        ckt
+    }
+
+    #[test]
+    fn tseitin_encoding_matches_eval() {
+        let ckt = all_gates_circuit();
+        let cnf = ckt.to_cnf(&[]).unwrap();
+
+        for x in 0..4u64 {
+            let inputs = [x & 1 == 1, x & 2 == 2];
+            let assn = ckt.eval(&inputs);
+            assert!(cnf.is_satisfied_by(&assn.wires));
+
+            // Flipping any gate output must violate the encoding.
+            for &o in &ckt.outputs {
+                let mut wires = assn.wires.clone();
+                wires[o as usize] = !wires[o as usize];
+                assert!(!cnf.is_satisfied_by(&wires));
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_wires_are_unit_clauses() {
+        let ckt = all_gates_circuit();
+        // AND output fixed to one forces both inputs to one.
+        let cnf = ckt.to_cnf(&[(5, true)]).unwrap();
+
+        let assn = ckt.eval(&[true, true]);
+        assert!(cnf.is_satisfied_by(&assn.wires));
+
+        let assn = ckt.eval(&[true, false]);
+        assert!(!cnf.is_satisfied_by(&assn.wires));
+
+        // Wire 10 is the last one
+        assert!(ckt.to_cnf(&[(10, false)]).is_ok());
+        assert!(ckt.to_cnf(&[(11, false)]).is_err());
+    }
+}
@@ -3,7 +3,18 @@ use std::{
     io::{BufRead, BufReader, Error, ErrorKind},
 };
 
-#[derive(Clone, Default, Debug)]
+mod cnf;
+pub use cnf::Cnf;
+
+mod sat;
+
+mod partition;
+pub use partition::{Partition, CUT_INPUT_PORT, CUT_OUTPUT_PORT};
+
+#[cfg(test)]
+pub(crate) mod arbitrary;
+
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
 pub struct BooleanCircuitGateIo {
     pub gate: u64,
     pub l_idx: u64,
@@ -14,7 +25,9 @@ pub struct BooleanCircuitGateIo {
 #[derive(Clone, Default, Debug)]
 pub struct BooleanCircuit {
     pub inputs: Vec<u64>,
+    pub input_names: Vec<String>,
     pub outputs: Vec<u64>,
+    pub output_names: Vec<String>,
     pub gates: Vec<BooleanCircuitGateIo>,
     pub max_wire_idx: u64,
 }
@@ -36,7 +49,9 @@ impl BooleanCircuit {
         let mut reader = BufReader::new(file);
 
         let mut inputs = Vec::new();
+        let mut input_names = Vec::new();
         let mut outputs = Vec::new();
+        let mut output_names = Vec::new();
         let mut gates = Vec::new();
         let mut max_wire_idx: u64 = 0;
 
@@ -74,6 +89,7 @@ impl BooleanCircuit {
                         )
                     })?;
                     inputs.push(input_idx);
+                    input_names.push(input_vec.get(1).unwrap_or(&"").to_string());
                 }
                 2 => {
                     let output_vec = line.split_ascii_whitespace().collect::<Vec<&str>>();
@@ -84,6 +100,7 @@ impl BooleanCircuit {
                         )
                     })?;
                     outputs.push(output_idx);
+                    output_names.push(output_vec.get(1).unwrap_or(&"").to_string());
                 }
                 3 => {
                     let wiring_vec = line.split_ascii_whitespace().collect::<Vec<&str>>();
@@ -138,7 +155,9 @@ impl BooleanCircuit {
 
         Ok(BooleanCircuit {
             inputs,
+            input_names,
             outputs,
+            output_names,
             gates,
             max_wire_idx,
         })
@@ -169,6 +188,36 @@ impl BooleanCircuit {
 
         BooleanCircuitAssignment { wires }
     }
+
+    /// Finds primary input values for which the circuit evaluates to the given outputs,
+    /// or `None` if no such inputs exist.
+    ///
+    /// Fails if there are not as many output values as outputs.
+    pub fn find_inputs(&self, outputs: &[bool]) -> Result<Option<Vec<bool>>, Error> {
+        if outputs.len() != self.outputs.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "{} output values given, the circuit has {} outputs",
+                    outputs.len(),
+                    self.outputs.len()
+                ),
+            ));
+        }
+
+        let fixed = self
+            .outputs
+            .iter()
+            .copied()
+            .zip(outputs.iter().copied())
+            .collect::<Vec<_>>();
+        let inputs = self
+            .to_cnf(&fixed)?
+            .solve()
+            .map(|wires| self.inputs.iter().map(|&i| wires[i as usize]).collect());
+
+        Ok(inputs)
+    }
 }
 
 impl BooleanCircuitInstance {
@@ -176,4 +225,16 @@ impl BooleanCircuitInstance {
         let assn = ckt.eval(inputs);
         BooleanCircuitInstance { ckt, assn }
     }
+
+    /// Builds an instance from a satisfying input assignment found for the given outputs,
+    /// or `None` if no inputs produce them.
+    ///
+    /// Fails if there are not as many output values as outputs.
+    pub fn from_ckt_and_outputs(
+        ckt: BooleanCircuit,
+        outputs: &[bool],
+    ) -> Result<Option<Self>, Error> {
+        let inputs = ckt.find_inputs(outputs)?;
+        Ok(inputs.map(|inputs| Self::from_ckt_and_inputs(ckt, &inputs)))
+    }
 }
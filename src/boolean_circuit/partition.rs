@@ -0,0 +1,257 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+};
+
+use super::{BooleanCircuit, BooleanCircuitGateIo, BooleanCircuitInstance};
+
+/// Input port of a part receiving the cut wires of the previous part.
+pub const CUT_INPUT_PORT: &str = "cut_in";
+/// Output port of a part passing its cut wires to the next part.
+pub const CUT_OUTPUT_PORT: &str = "cut_out";
+
+/// Gate type copying its input when both inputs are the same wire
+const AND: u64 = 2;
+
+/// A circuit split at cut wires into parts which are evaluated, and proven, one after the
+/// other.
+///
+/// Each part receives on its `cut_in` port the wires still needed from the previous parts,
+/// and passes on its `cut_out` port the wires needed by the next parts; the two ports of
+/// every part are `cut_width` wires wide, padded with unused wires. All the primary inputs
+/// enter the first part, and each primary output leaves the part driving it, or the first
+/// part if no gate drives it. The ports keep the names they have in the whole circuit.
+#[derive(Clone, Debug)]
+pub struct Partition {
+    /// Parts in evaluation order, each with wire indices of its own: the `cut_in` wires
+    /// first, then the `cut_out` wires, then the others
+    pub parts: Vec<BooleanCircuit>,
+    pub cut_width: usize,
+}
+
+impl BooleanCircuit {
+    /// Splits the gates, in order, into parts of at most `max_gates` gates, plus a copy
+    /// gate for each cut wire a part only passes through.
+    ///
+    /// Fails if `max_gates` is zero, or if a primary port has the name of a cut port.
+    pub fn partition(&self, max_gates: usize) -> Result<Partition, Error> {
+        if max_gates == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "parts need room for at least one gate",
+            ));
+        }
+
+        let port = |name: &String| name.split('[').next().unwrap_or("").to_string();
+        if let Some(name) = self
+            .input_names
+            .iter()
+            .chain(self.output_names.iter())
+            .find(|name| port(name) == CUT_INPUT_PORT || port(name) == CUT_OUTPUT_PORT)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("port {} has the name of a cut port", name),
+            ));
+        }
+
+        let mut chunks = self.gates.chunks(max_gates).collect::<Vec<_>>();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+
+        // Part each wire is available from: the part driving it, or the first part for
+        // primary inputs and undriven wires; and the last part reading it
+        let num_wires = (self.max_wire_idx + 1) as usize;
+        let mut driven = vec![false; num_wires];
+        let mut driver = vec![0; num_wires];
+        let mut last_reader = vec![0; num_wires];
+        for (i, chunk) in chunks.iter().enumerate() {
+            for gate_io in chunk.iter() {
+                driven[gate_io.o_idx as usize] = true;
+                driver[gate_io.o_idx as usize] = i;
+                for w in [gate_io.l_idx, gate_io.r_idx] {
+                    last_reader[w as usize] = i;
+                }
+            }
+        }
+
+        // Wires crossing each cut, in increasing order
+        let mut cuts = vec![Vec::new(); chunks.len() - 1];
+        for w in 0..num_wires {
+            if driver[w] < last_reader[w] {
+                for cut in cuts[driver[w]..last_reader[w]].iter_mut() {
+                    cut.push(w as u64);
+                }
+            }
+        }
+        let cut_width = cuts.iter().map(Vec::len).max().unwrap_or(0);
+
+        let parts = chunks
+            .iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                let cut_in: &[u64] = if i > 0 { &cuts[i - 1] } else { &[] };
+                let cut_out = cuts.get(i).map(Vec::as_slice).unwrap_or_default();
+
+                let mut wires = HashMap::new();
+                for (slot, &w) in cut_in.iter().enumerate() {
+                    wires.insert(w, slot as u64);
+                }
+                for (slot, &w) in cut_out.iter().enumerate() {
+                    if driven[w as usize] && driver[w as usize] == i {
+                        wires.insert(w, (cut_width + slot) as u64);
+                    }
+                }
+                let mut num_part_wires = 2 * cut_width as u64;
+                let mut local = |w: u64| {
+                    *wires.entry(w).or_insert_with(|| {
+                        num_part_wires += 1;
+                        num_part_wires - 1
+                    })
+                };
+
+                let mut part = BooleanCircuit::default();
+
+                for slot in 0..cut_width {
+                    part.inputs.push(slot as u64);
+                    part.input_names
+                        .push(format!("{}[{}]", CUT_INPUT_PORT, slot));
+                }
+                if i == 0 {
+                    for (&w, name) in self.inputs.iter().zip(self.input_names.iter()) {
+                        part.inputs.push(local(w));
+                        part.input_names.push(name.clone());
+                    }
+                }
+
+                for gate_io in chunk.iter() {
+                    part.gates.push(BooleanCircuitGateIo {
+                        gate: gate_io.gate,
+                        l_idx: local(gate_io.l_idx),
+                        r_idx: local(gate_io.r_idx),
+                        o_idx: local(gate_io.o_idx),
+                    });
+                }
+                for (slot, &w) in cut_out.iter().enumerate() {
+                    if !(driven[w as usize] && driver[w as usize] == i) {
+                        let idx = local(w);
+                        part.gates.push(BooleanCircuitGateIo {
+                            gate: AND,
+                            l_idx: idx,
+                            r_idx: idx,
+                            o_idx: (cut_width + slot) as u64,
+                        });
+                    }
+                }
+
+                for (&w, name) in self.outputs.iter().zip(self.output_names.iter()) {
+                    if driver[w as usize] == i {
+                        part.outputs.push(local(w));
+                        part.output_names.push(name.clone());
+                    }
+                }
+                for slot in 0..cut_width {
+                    part.outputs.push((cut_width + slot) as u64);
+                    part.output_names
+                        .push(format!("{}[{}]", CUT_OUTPUT_PORT, slot));
+                }
+
+                part.max_wire_idx = num_part_wires.max(1) - 1;
+                part
+            })
+            .collect();
+
+        Ok(Partition { parts, cut_width })
+    }
+}
+
+impl Partition {
+    /// Evaluates the parts in turn on the primary inputs, each receiving the `cut_out`
+    /// wires of the previous one.
+    pub fn eval(&self, inputs: &[bool]) -> Vec<BooleanCircuitInstance> {
+        let mut cut = vec![false; self.cut_width];
+
+        self.parts
+            .iter()
+            .enumerate()
+            .map(|(i, part)| {
+                let part_inputs = if i == 0 {
+                    [&cut[..], inputs].concat()
+                } else {
+                    cut.clone()
+                };
+                let inst = BooleanCircuitInstance::from_ckt_and_inputs(part.clone(), &part_inputs);
+
+                cut = inst.assn.wires[self.cut_width..2 * self.cut_width].to_vec();
+                inst
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::boolean_circuit::arbitrary::arb_boolean_circuit_instance;
+
+    /// Named primary output values of the whole circuit, or of the parts.
+    fn named_outputs(insts: &[BooleanCircuitInstance]) -> Vec<(String, bool)> {
+        let mut outputs = insts
+            .iter()
+            .flat_map(|inst| {
+                inst.ckt
+                    .outputs
+                    .iter()
+                    .zip(inst.ckt.output_names.iter())
+                    .filter(|(_, name)| !name.starts_with(CUT_OUTPUT_PORT))
+                    .map(|(&w, name)| (name.clone(), inst.assn.wires[w as usize]))
+            })
+            .collect::<Vec<_>>();
+        outputs.sort();
+        outputs
+    }
+
+    #[test]
+    fn parts_are_bounded() {
+        let ckt = BooleanCircuit::from_netlist("examples/cla_adder_6b.zkt").unwrap();
+        let partition = ckt.partition(10).unwrap();
+
+        assert_eq!(partition.parts.len(), ckt.gates.len().div_ceil(10));
+        let cut_width = partition.cut_width as u64;
+        assert!(cut_width > 0);
+        for part in partition.parts.iter() {
+            assert!(part.gates.len() <= 10 + partition.cut_width);
+
+            // The cut ports are at the same wires in every part
+            let cut_in = part.inputs[..partition.cut_width].to_vec();
+            let cut_out = part.outputs[part.outputs.len() - partition.cut_width..].to_vec();
+            assert_eq!(cut_in, (0..cut_width).collect::<Vec<_>>());
+            assert_eq!(cut_out, (cut_width..2 * cut_width).collect::<Vec<_>>());
+        }
+        assert!(ckt.partition(0).is_err());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn parts_evaluate_like_the_whole_circuit(
+            inst in arb_boolean_circuit_instance(1..=8, 1..=40),
+            max_gates in 1usize..=12,
+        ) {
+            let partition = inst.ckt.partition(max_gates).unwrap();
+            let inputs = inst
+                .ckt
+                .inputs
+                .iter()
+                .map(|&w| inst.assn.wires[w as usize])
+                .collect::<Vec<_>>();
+
+            let parts = partition.eval(&inputs);
+            prop_assert_eq!(named_outputs(&parts), named_outputs(&[inst]));
+        }
+    }
+}
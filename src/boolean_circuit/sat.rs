@@ -0,0 +1,365 @@
+//! A small conflict-driven clause-learning SAT solver for [`Cnf`] formulas.
+//!
+//! Uses two watched literals for propagation, first-UIP clause learning, activity-based
+//! branching with phase saving, and geometric restarts.
+
+use super::cnf::Cnf;
+
+/// Literal over a zero-based variable `v`: `2 * v` is positive, `2 * v + 1` negative.
+type Lit = usize;
+
+fn lit_from_dimacs(lit: i64) -> Lit {
+    let var = (lit.unsigned_abs() - 1) as usize;
+    2 * var + (lit < 0) as usize
+}
+
+fn var(lit: Lit) -> usize {
+    lit >> 1
+}
+
+fn neg(lit: Lit) -> Lit {
+    lit ^ 1
+}
+
+struct Solver {
+    clauses: Vec<Vec<Lit>>,
+    watches: Vec<Vec<usize>>,
+    assigns: Vec<Option<bool>>,
+    level: Vec<usize>,
+    reason: Vec<Option<usize>>,
+    trail: Vec<Lit>,
+    trail_lim: Vec<usize>,
+    qhead: usize,
+    activity: Vec<f64>,
+    var_inc: f64,
+    polarity: Vec<bool>,
+    seen: Vec<bool>,
+}
+
+impl Solver {
+    fn new(num_vars: usize) -> Self {
+        Self {
+            clauses: Vec::new(),
+            watches: vec![Vec::new(); 2 * num_vars],
+            assigns: vec![None; num_vars],
+            level: vec![0; num_vars],
+            reason: vec![None; num_vars],
+            trail: Vec::new(),
+            trail_lim: Vec::new(),
+            qhead: 0,
+            activity: vec![0.0; num_vars],
+            var_inc: 1.0,
+            polarity: vec![false; num_vars],
+            seen: vec![false; num_vars],
+        }
+    }
+
+    fn lit_value(&self, lit: Lit) -> Option<bool> {
+        self.assigns[var(lit)].map(|val| val ^ (lit & 1 == 1))
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_lim.len()
+    }
+
+    fn enqueue(&mut self, lit: Lit, reason: Option<usize>) {
+        let v = var(lit);
+        self.assigns[v] = Some(lit & 1 == 0);
+        self.level[v] = self.decision_level();
+        self.reason[v] = reason;
+        self.trail.push(lit);
+    }
+
+    fn attach(&mut self, lits: Vec<Lit>) -> usize {
+        let clause_idx = self.clauses.len();
+        self.watches[lits[0]].push(clause_idx);
+        self.watches[lits[1]].push(clause_idx);
+        self.clauses.push(lits);
+        clause_idx
+    }
+
+    /// Adds an input clause at decision level zero. Returns false if the formula became
+    /// trivially unsatisfiable.
+    fn add_clause(&mut self, mut lits: Vec<Lit>) -> bool {
+        lits.sort_unstable();
+        lits.dedup();
+
+        // Tautologies contain both polarities of a variable, which sort next to each other
+        if lits.windows(2).any(|w| neg(w[0]) == w[1]) {
+            return true;
+        }
+        if lits.iter().any(|&l| self.lit_value(l) == Some(true)) {
+            return true;
+        }
+        lits.retain(|&l| self.lit_value(l).is_none());
+
+        match lits.len() {
+            0 => false,
+            1 => {
+                self.enqueue(lits[0], None);
+                true
+            }
+            _ => {
+                self.attach(lits);
+                true
+            }
+        }
+    }
+
+    /// Propagates all enqueued assignments. Returns the index of a conflicting clause, if any.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.qhead < self.trail.len() {
+            let false_lit = neg(self.trail[self.qhead]);
+            self.qhead += 1;
+
+            let mut watchers = std::mem::take(&mut self.watches[false_lit]);
+            let mut conflict = None;
+            let mut i = 0;
+
+            while i < watchers.len() {
+                let clause_idx = watchers[i];
+
+                // Keep the falsified watch in the second position
+                if self.clauses[clause_idx][0] == false_lit {
+                    self.clauses[clause_idx].swap(0, 1);
+                }
+
+                let first = self.clauses[clause_idx][0];
+                if self.lit_value(first) == Some(true) {
+                    i += 1;
+                    continue;
+                }
+
+                let new_watch = (2..self.clauses[clause_idx].len())
+                    .find(|&k| self.lit_value(self.clauses[clause_idx][k]) != Some(false));
+
+                match new_watch {
+                    Some(k) => {
+                        self.clauses[clause_idx].swap(1, k);
+                        let lit = self.clauses[clause_idx][1];
+                        self.watches[lit].push(clause_idx);
+                        watchers.swap_remove(i);
+                    }
+                    None if self.lit_value(first) == Some(false) => {
+                        conflict = Some(clause_idx);
+                        break;
+                    }
+                    None => {
+                        self.enqueue(first, Some(clause_idx));
+                        i += 1;
+                    }
+                }
+            }
+
+            self.watches[false_lit] = watchers;
+
+            if conflict.is_some() {
+                self.qhead = self.trail.len();
+                return conflict;
+            }
+        }
+
+        None
+    }
+
+    fn bump(&mut self, v: usize) {
+        self.activity[v] += self.var_inc;
+        if self.activity[v] > 1e100 {
+            self.activity.iter_mut().for_each(|a| *a *= 1e-100);
+            self.var_inc *= 1e-100;
+        }
+    }
+
+    /// Derives the first-UIP clause from a conflict. The asserting literal is placed first
+    /// and the literal with the highest remaining decision level second. Returns the
+    /// learnt clause and the level to backtrack to.
+    fn analyze(&mut self, mut conflict: usize) -> (Vec<Lit>, usize) {
+        let mut learnt = vec![0];
+        let mut pending = 0;
+        let mut uip: Option<Lit> = None;
+        let mut trail_idx = self.trail.len();
+
+        loop {
+            // The implied literal of a reason clause is its first literal; skip it
+            let skip = if uip.is_some() { 1 } else { 0 };
+            for k in skip..self.clauses[conflict].len() {
+                let q = self.clauses[conflict][k];
+                let v = var(q);
+                if !self.seen[v] && self.level[v] > 0 {
+                    self.seen[v] = true;
+                    self.bump(v);
+                    if self.level[v] >= self.decision_level() {
+                        pending += 1;
+                    } else {
+                        learnt.push(q);
+                    }
+                }
+            }
+
+            loop {
+                trail_idx -= 1;
+                if self.seen[var(self.trail[trail_idx])] {
+                    break;
+                }
+            }
+
+            let p = self.trail[trail_idx];
+            self.seen[var(p)] = false;
+            uip = Some(p);
+            pending -= 1;
+
+            if pending == 0 {
+                break;
+            }
+            conflict = self.reason[var(p)].expect("only the decision literal lacks a reason");
+        }
+
+        learnt[0] = neg(uip.unwrap());
+        for &l in &learnt[1..] {
+            self.seen[var(l)] = false;
+        }
+
+        let backtrack_level = if learnt.len() == 1 {
+            0
+        } else {
+            let max_k = (1..learnt.len())
+                .max_by_key(|&k| self.level[var(learnt[k])])
+                .unwrap();
+            learnt.swap(1, max_k);
+            self.level[var(learnt[1])]
+        };
+
+        (learnt, backtrack_level)
+    }
+
+    fn cancel_until(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+
+        let lim = self.trail_lim[level];
+        for &lit in &self.trail[lim..] {
+            let v = var(lit);
+            self.polarity[v] = self.assigns[v].unwrap();
+            self.assigns[v] = None;
+            self.reason[v] = None;
+        }
+        self.trail.truncate(lim);
+        self.trail_lim.truncate(level);
+        self.qhead = lim;
+    }
+
+    fn pick_branch_var(&self) -> Option<usize> {
+        (0..self.assigns.len())
+            .filter(|&v| self.assigns[v].is_none())
+            .max_by(|&a, &b| self.activity[a].total_cmp(&self.activity[b]))
+    }
+
+    fn search(&mut self) -> Option<Vec<bool>> {
+        let mut conflicts = 0;
+        let mut restart_limit = 100;
+
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.decision_level() == 0 {
+                    return None;
+                }
+
+                conflicts += 1;
+                let (learnt, backtrack_level) = self.analyze(conflict);
+                self.cancel_until(backtrack_level);
+
+                if learnt.len() == 1 {
+                    self.enqueue(learnt[0], None);
+                } else {
+                    let asserting = learnt[0];
+                    let clause_idx = self.attach(learnt);
+                    self.enqueue(asserting, Some(clause_idx));
+                }
+
+                self.var_inc /= 0.95;
+            } else if conflicts >= restart_limit {
+                conflicts = 0;
+                restart_limit += restart_limit / 2;
+                self.cancel_until(0);
+            } else {
+                match self.pick_branch_var() {
+                    Some(v) => {
+                        self.trail_lim.push(self.trail.len());
+                        self.enqueue(2 * v + (!self.polarity[v]) as usize, None);
+                    }
+                    None => return Some(self.assigns.iter().map(|a| a.unwrap()).collect()),
+                }
+            }
+        }
+    }
+}
+
+impl Cnf {
+    /// Searches for a satisfying assignment of the formula.
+    ///
+    /// Returns the value of every variable indexed from zero (i.e. by wire index for a
+    /// formula from [`BooleanCircuit::to_cnf`]), or `None` if the formula is unsatisfiable.
+    ///
+    /// [`BooleanCircuit::to_cnf`]: super::BooleanCircuit::to_cnf
+    pub fn solve(&self) -> Option<Vec<bool>> {
+        let mut solver = Solver::new(self.num_vars as usize);
+
+        for clause in &self.clauses {
+            let lits = clause.iter().map(|&lit| lit_from_dimacs(lit)).collect();
+            if !solver.add_clause(lits) {
+                return None;
+            }
+        }
+
+        solver.search()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::boolean_circuit::{BooleanCircuit, BooleanCircuitInstance};
+
+    use super::*;
+
+    fn u64_to_bits_le(x: u64, n: usize) -> Vec<bool> {
+        (0..n).map(|i| (x >> i) & 1 == 1).collect()
+    }
+
+    #[test]
+    fn solve_small_formulas() {
+        let sat = Cnf {
+            num_vars: 3,
+            clauses: vec![vec![1, 2], vec![-1, 3], vec![-2, -3], vec![-3, 1]],
+        };
+        let model = sat.solve().unwrap();
+        assert!(sat.is_satisfied_by(&model));
+
+        let unsat = Cnf {
+            num_vars: 2,
+            clauses: vec![vec![1, 2], vec![-1, 2], vec![1, -2], vec![-1, -2]],
+        };
+        assert!(unsat.solve().is_none());
+    }
+
+    #[test]
+    fn find_adder_inputs_for_sum() {
+        let ckt = BooleanCircuit::from_netlist("examples/cla_adder_6b.zkt").unwrap();
+
+        // s = 45, c_out = 1
+        let outputs = [u64_to_bits_le(45, 6), u64_to_bits_le(1, 1)].concat();
+        let inst = BooleanCircuitInstance::from_ckt_and_outputs(ckt.clone(), &outputs)
+            .unwrap()
+            .unwrap();
+
+        let found = inst
+            .ckt
+            .outputs
+            .iter()
+            .map(|&o| inst.assn.wires[o as usize])
+            .collect::<Vec<_>>();
+        assert_eq!(found, outputs);
+
+        assert!(ckt.find_inputs(&outputs[1..]).is_err());
+    }
+}
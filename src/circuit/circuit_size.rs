@@ -0,0 +1,288 @@
+use halo2_proofs::plonk::{Circuit, ConstraintSystem};
+use halo2curves::bn256::Fr;
+
+use crate::boolean_circuit::BooleanCircuit;
+
+use super::field::ZktSimField;
+use super::gate_definition_table::GATE_DEFINITION_TABLE_ROWS;
+use super::mimc7_cbc_cipher::Mimc7CbcCipherConfig;
+use super::native::{IDX_BITS, ROWS_PER_BLOCK};
+use super::netlist_binding::{
+    NetlistBinding, NetlistBindingConfig, NetlistBindingMode, PoseidonSpongeConfig,
+};
+use super::poseidon::{commit_rows, hash_rows};
+use super::visibility::IoVisibility;
+use super::ZktSimCircuit;
+
+/// Capacity of a zktsim circuit.
+///
+/// The verifying key only depends on the size, the netlist binding mode and the number of
+/// committed wires, so one key serves every netlist which fits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitSize {
+    /// The circuit has `2^k` rows
+    pub k: u32,
+    /// Rows of the gate-i/o table, i.e. the maximum number of gates; a multiple of the
+    /// netlist rows per block
+    pub num_gate_rows: usize,
+    /// Rows of the wire assignment table, i.e. the maximum number of wires, at most `2^27`
+    pub num_wire_rows: usize,
+    /// Input vectors proven at once, each with its own gate-i/o and wire assignment rows;
+    /// one unless proving batches
+    pub batch_size: usize,
+}
+
+impl CircuitSize {
+    /// Smallest size fitting the given circuit.
+    pub fn new<F: ZktSimField>(
+        ckt: &BooleanCircuit,
+        visibility: &IoVisibility<F>,
+        binding: &NetlistBinding<F>,
+    ) -> Self {
+        let max_committed_wires = visibility
+            .committed_wires(ckt)
+            .iter()
+            .map(Vec::len)
+            .max()
+            .unwrap_or(0);
+
+        Self::with_limits(
+            ckt.gates.len(),
+            ckt.max_wire_idx as usize + 1,
+            max_committed_wires,
+            binding.mode(),
+        )
+    }
+
+    /// Smallest size fitting any circuit with at most `max_gates` gates, `max_wires` wires,
+    /// and at most `max_committed_wires` committed inputs and committed outputs each.
+    ///
+    /// Panics if `max_wires` exceeds the `2^27` wire indices of the netlist packing.
+    pub fn with_limits(
+        max_gates: usize,
+        max_wires: usize,
+        max_committed_wires: usize,
+        binding: NetlistBindingMode,
+    ) -> Self {
+        Self::sized(max_gates, max_wires, max_committed_wires, 1, binding)
+    }
+
+    /// Smallest size proving batches of up to `batch_size` input vectors of the given
+    /// circuit.
+    pub fn new_batch<F: ZktSimField>(
+        ckt: &BooleanCircuit,
+        binding: &NetlistBinding<F>,
+        batch_size: usize,
+    ) -> Self {
+        Self::with_batch_limits(
+            ckt.gates.len(),
+            ckt.max_wire_idx as usize + 1,
+            batch_size,
+            binding.mode(),
+        )
+    }
+
+    /// Smallest size proving batches of up to `batch_size` input vectors of any circuit
+    /// with at most `max_gates` gates and `max_wires` wires.
+    ///
+    /// Panics if `batch_size` is zero or `max_wires` exceeds the `2^27` wire indices of the
+    /// netlist packing.
+    pub fn with_batch_limits(
+        max_gates: usize,
+        max_wires: usize,
+        batch_size: usize,
+        binding: NetlistBindingMode,
+    ) -> Self {
+        assert!(batch_size > 0, "batches hold at least one input vector");
+        Self::sized(max_gates, max_wires, 0, batch_size, binding)
+    }
+
+    fn sized(
+        max_gates: usize,
+        max_wires: usize,
+        max_committed_wires: usize,
+        batch_size: usize,
+        binding: NetlistBindingMode,
+    ) -> Self {
+        let num_gate_rows = max_gates.max(1).next_multiple_of(ROWS_PER_BLOCK);
+        let num_wire_rows = max_wires.max(1);
+        assert!(
+            num_wire_rows <= 1 << IDX_BITS,
+            "at most 2^{} wires are supported",
+            IDX_BITS
+        );
+
+        let rows = rows_needed(
+            num_gate_rows,
+            num_wire_rows,
+            [max_committed_wires; 2],
+            batch_size,
+            binding,
+        ) + unusable_rows(binding);
+        let k = rows.next_power_of_two().trailing_zeros();
+
+        Self {
+            k,
+            num_gate_rows,
+            num_wire_rows,
+            batch_size,
+        }
+    }
+
+    /// Checks whether the circuit, with the given committed wires, fits in this size.
+    pub fn fits<F: ZktSimField>(
+        &self,
+        ckt: &BooleanCircuit,
+        visibility: &IoVisibility<F>,
+        binding: &NetlistBinding<F>,
+    ) -> bool {
+        let committed = visibility.committed_wires(ckt).map(|wires| wires.len());
+        let rows = rows_needed(
+            self.num_gate_rows,
+            self.num_wire_rows,
+            committed,
+            self.batch_size,
+            binding.mode(),
+        ) + unusable_rows(binding.mode());
+
+        self.batch_size > 0
+            && self.num_gate_rows % ROWS_PER_BLOCK == 0
+            && self.num_wire_rows <= 1 << IDX_BITS
+            && ckt.gates.len() <= self.num_gate_rows
+            && (ckt.max_wire_idx as usize) < self.num_wire_rows
+            && rows <= 1 << self.k
+    }
+
+    /// Checks whether batches of `num_vectors` input vectors of the circuit fit in this
+    /// size.
+    pub fn fits_batch<F: ZktSimField>(
+        &self,
+        ckt: &BooleanCircuit,
+        binding: &NetlistBinding<F>,
+        num_vectors: usize,
+    ) -> bool {
+        (1..=self.batch_size).contains(&num_vectors)
+            && self.fits(ckt, &IoVisibility::all_public(), binding)
+    }
+}
+
+/// Rows used by the tables, the netlist binding and the wire commitments. The Poseidon
+/// regions are stacked in the same columns, followed by the constants.
+fn rows_needed(
+    num_gate_rows: usize,
+    num_wire_rows: usize,
+    num_committed_wires: [usize; 2],
+    batch_size: usize,
+    binding: NetlistBindingMode,
+) -> usize {
+    let commitment_rows = num_committed_wires
+        .iter()
+        .filter(|&&n| n > 0)
+        .map(|&n| commit_rows(n + 1))
+        .sum::<usize>();
+
+    let binding_rows = match binding {
+        // Key hash, and the zero initialization value constant of the cipher
+        NetlistBindingMode::Mimc7Cbc => hash_rows() + 1,
+        // Salt followed by one word per block
+        NetlistBindingMode::PoseidonSponge => commit_rows(num_gate_rows / ROWS_PER_BLOCK + 1),
+    };
+
+    [
+        num_gate_rows * batch_size,
+        // The wire assignment table ends with a disabled row
+        num_wire_rows * batch_size + 1,
+        GATE_DEFINITION_TABLE_ROWS,
+        commitment_rows + binding_rows,
+    ]
+    .into_iter()
+    .max()
+    .unwrap()
+}
+
+/// Rows reserved by the proving system at the end of the circuit for blinding. They only
+/// depend on the columns and gates, which are the same over every field.
+fn unusable_rows(binding: NetlistBindingMode) -> usize {
+    fn unusable_rows_with<B: NetlistBindingConfig<Fr>>() -> usize {
+        let mut meta = ConstraintSystem::<Fr>::default();
+        ZktSimCircuit::<Fr, B>::configure(&mut meta);
+        meta.minimum_rows() - 1
+    }
+
+    match binding {
+        NetlistBindingMode::Mimc7Cbc => unusable_rows_with::<Mimc7CbcCipherConfig<Fr>>(),
+        NetlistBindingMode::PoseidonSponge => unusable_rows_with::<PoseidonSpongeConfig>(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boolean_circuit::BooleanCircuitInstance;
+    use crate::circuit::{run_mock_prover, EncryptionKey};
+    use halo2curves::pasta::pallas::Base as Fp;
+
+    fn cla_adder() -> BooleanCircuitInstance {
+        let ckt = BooleanCircuit::from_netlist("examples/cla_adder_6b.zkt").unwrap();
+        let inputs = vec![true; ckt.inputs.len()];
+        BooleanCircuitInstance::from_ckt_and_inputs(ckt, &inputs)
+    }
+
+    #[test]
+    fn derived_size_is_satisfiable() {
+        let visibility = IoVisibility::committed_inputs(&["a", "b"], Fr::from(7));
+        let bindings = [
+            NetlistBinding::Mimc7Cbc(EncryptionKey::random()),
+            NetlistBinding::PoseidonSponge { salt: Fr::from(3) },
+        ];
+
+        for binding in bindings {
+            let inst = cla_adder();
+            let size = CircuitSize::new(&inst.ckt, &visibility, &binding);
+            assert!(size.fits(&inst.ckt, &visibility, &binding));
+            assert!(size.num_gate_rows >= inst.ckt.gates.len());
+
+            run_mock_prover(inst, &visibility, &binding, &size);
+        }
+    }
+
+    #[test]
+    fn limits_bound_the_fitting_circuits() {
+        let inst = cla_adder();
+        let visibility = IoVisibility::<Fr>::all_public();
+        let binding = NetlistBinding::Mimc7Cbc(EncryptionKey::random());
+
+        let size = CircuitSize::with_limits(1 << 10, 1 << 10, 0, binding.mode());
+        assert!(size.fits(&inst.ckt, &visibility, &binding));
+        assert!(size.k >= CircuitSize::new(&inst.ckt, &visibility, &binding).k);
+
+        let too_small = CircuitSize::with_limits(4, 1 << 10, 0, binding.mode());
+        assert!(!too_small.fits(&inst.ckt, &visibility, &binding));
+    }
+
+    #[test]
+    fn batch_sizes_grow_with_the_batch() {
+        let inst = cla_adder();
+        let binding = NetlistBinding::PoseidonSponge { salt: Fr::from(3) };
+
+        let single = CircuitSize::new(&inst.ckt, &IoVisibility::all_public(), &binding);
+        let batch = CircuitSize::new_batch(&inst.ckt, &binding, 64);
+        assert_eq!(CircuitSize::new_batch(&inst.ckt, &binding, 1), single);
+        assert_eq!(batch.num_gate_rows, single.num_gate_rows);
+        assert!(batch.k > single.k);
+
+        assert!(batch.fits_batch(&inst.ckt, &binding, 64));
+        assert!(!batch.fits_batch(&inst.ckt, &binding, 65));
+        assert!(!batch.fits_batch(&inst.ckt, &binding, 0));
+    }
+
+    #[test]
+    fn derived_size_is_satisfiable_over_pasta() {
+        let inst = cla_adder();
+        let visibility = IoVisibility::committed_inputs(&["a"], Fp::from(7));
+        let binding = NetlistBinding::Mimc7Cbc(EncryptionKey::random());
+
+        let size = CircuitSize::new(&inst.ckt, &visibility, &binding);
+        run_mock_prover(inst, &visibility, &binding, &size);
+    }
+}
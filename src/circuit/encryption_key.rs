@@ -0,0 +1,72 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+};
+
+use halo2curves::bn256::Fr;
+use rand_core::{CryptoRng, OsRng, RngCore};
+
+use super::field::ZktSimField;
+
+/// Secret key of the MiMC7 CBC netlist encryption.
+///
+/// Anyone holding the key can decrypt the netlist from the public ciphertext; persist it
+/// only where the netlist owner can later reveal it or reuse it across proofs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncryptionKey<F = Fr>(pub(crate) F);
+
+impl<F: ZktSimField> EncryptionKey<F> {
+    /// Samples a fresh key from the operating system CSPRNG.
+    pub fn random() -> Self {
+        Self::random_with(OsRng)
+    }
+
+    pub fn random_with(rng: impl RngCore + CryptoRng) -> Self {
+        Self(F::random(rng))
+    }
+
+    pub fn from_field(key: F) -> Self {
+        Self(key)
+    }
+
+    pub fn to_field(&self) -> F {
+        self.0
+    }
+
+    /// Little-endian canonical encoding of the key.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.to_repr()
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> Option<Self> {
+        Option::from(F::from_repr(bytes)).map(Self)
+    }
+
+    /// Reads a key stored as 64 hex digits by [`EncryptionKey::write_to_file`].
+    pub fn from_file(file_name: &str) -> Result<Self, Error> {
+        let invalid = || Error::new(ErrorKind::InvalidData, "invalid encryption key file");
+
+        let hex = fs::read_to_string(file_name)?;
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+        }
+
+        Self::from_bytes(bytes).ok_or_else(invalid)
+    }
+
+    pub fn write_to_file(&self, file_name: &str) -> Result<(), Error> {
+        let hex = self
+            .to_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+
+        fs::write(file_name, hex + "\n")
+    }
+}
@@ -8,6 +8,7 @@ use halo2curves::ff::PrimeField;
 pub(super) struct ExpectedIoTableConfig<F: PrimeField> {
     pub(super) enable_i_o: Column<Instance>,
     pub(super) i_o_val: Column<Instance>,
+    pub(super) commitment: Column<Instance>,
 
     _marker: PhantomData<F>,
 }
@@ -15,6 +16,7 @@ pub(super) struct ExpectedIoTableConfig<F: PrimeField> {
 pub(super) struct ExpectedIoTableInstance {
     pub(super) enable_i_o: Column<Instance>,
     pub(super) i_o_val: Column<Instance>,
+    pub(super) commitment: Column<Instance>,
 }
 
 impl<F: PrimeField> ExpectedIoTableConfig<F> {
@@ -22,9 +24,12 @@ impl<F: PrimeField> ExpectedIoTableConfig<F> {
         meta: &mut ConstraintSystem<F>,
         instance: ExpectedIoTableInstance,
     ) -> Self {
+        meta.enable_equality(instance.commitment);
+
         Self {
             enable_i_o: instance.enable_i_o,
             i_o_val: instance.i_o_val,
+            commitment: instance.commitment,
             _marker: PhantomData,
         }
     }
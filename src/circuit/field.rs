@@ -0,0 +1,79 @@
+use ff::PrimeField;
+use halo2curves::{
+    bn256::Fr,
+    pasta::{pallas::Base as Fp, vesta::Base as Fq},
+};
+
+use crate::gadgets::poseidon::primitives::{Mds, P128Pow5T3, Spec};
+
+/// Scalar field a zktsim circuit can be built over: the BN256 scalar field, proven with
+/// KZG, or one of the Pasta fields, proven with IPA.
+///
+/// The field must hold the 252 bits of a packed netlist block, and `x^7` and `x^5` must
+/// permute it for MiMC7 and Poseidon.
+pub trait ZktSimField: PrimeField<Repr = [u8; 32]> {
+    /// Round constants, MDS matrix and inverse MDS matrix of Poseidon-128 over this field.
+    #[doc(hidden)]
+    fn poseidon_constants() -> (Vec<[Self; 3]>, [[Self; 3]; 3], [[Self; 3]; 3]);
+}
+
+impl ZktSimField for Fr {
+    fn poseidon_constants() -> (Vec<[Self; 3]>, [[Self; 3]; 3], [[Self; 3]; 3]) {
+        <P128Pow5T3 as Spec<Self, 3, 2>>::constants()
+    }
+}
+
+impl ZktSimField for Fp {
+    fn poseidon_constants() -> (Vec<[Self; 3]>, [[Self; 3]; 3], [[Self; 3]; 3]) {
+        <P128Pow5T3 as Spec<Self, 3, 2>>::constants()
+    }
+}
+
+impl ZktSimField for Fq {
+    fn poseidon_constants() -> (Vec<[Self; 3]>, [[Self; 3]; 3], [[Self; 3]; 3]) {
+        <P128Pow5T3 as Spec<Self, 3, 2>>::constants()
+    }
+}
+
+/// [`P128Pow5T3`] over any [`ZktSimField`]: Poseidon-128 with the `x^5` S-box, a width of
+/// three and the standard constants of the field.
+#[derive(Debug)]
+pub(super) struct Poseidon128;
+
+impl<F: ZktSimField> Spec<F, 3, 2> for Poseidon128 {
+    fn full_rounds() -> usize {
+        8
+    }
+
+    fn partial_rounds() -> usize {
+        56
+    }
+
+    fn sbox(val: F) -> F {
+        val.pow_vartime([5])
+    }
+
+    /// The constants are hard-coded, but they are the ones the reference implementation
+    /// generates with the first MDS it finds secure.
+    fn secure_mds() -> usize {
+        0
+    }
+
+    fn constants() -> (Vec<[F; 3]>, Mds<F, 3>, Mds<F, 3>) {
+        F::poseidon_constants()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gadgets::poseidon::primitives::generate_constants;
+
+    #[test]
+    fn secure_mds_generates_the_hard_coded_constants() {
+        assert_eq!(
+            generate_constants::<Fr, Poseidon128, 3, 2>(),
+            <Poseidon128 as Spec<Fr, 3, 2>>::constants()
+        );
+    }
+}
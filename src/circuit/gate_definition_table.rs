@@ -7,6 +7,12 @@ use halo2_proofs::{
 
 use halo2curves::ff::PrimeField;
 
+/// Gate types defined by the table, numbered from 1.
+pub(super) const NUM_GATE_TYPES: usize = 7;
+
+/// Rows of the gate-definition table: four per gate, then a zero row.
+pub(super) const GATE_DEFINITION_TABLE_ROWS: usize = NUM_GATE_TYPES * 4 + 1;
+
 #[derive(Debug, Clone)]
 pub(super) struct GateDefinitionTableConfig<F: PrimeField> {
     pub(super) internal_enable_gate_def: Column<Fixed>,
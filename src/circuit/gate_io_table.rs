@@ -2,15 +2,38 @@ use std::marker::PhantomData;
 
 use halo2_proofs::{
     circuit::{Layouter, Value},
-    plonk::{Advice, Assigned, Column, ConstraintSystem, Error, Fixed},
+    plonk::{
+        Advice, Assigned, Column, ConstraintSystem, Constraints, Error, Expression, Fixed, Instance,
+    },
+    poly::Rotation,
 };
 
 use halo2curves::ff::PrimeField;
 
+use crate::gadgets::utilities::range_check;
+
+use super::common::*;
+use super::native::IDX_BITS;
+
+/// Bits of each limb of a range-checked wire index
+const IDX_LIMB_BITS: usize = 3;
+/// Limbs of a wire index, least significant first
+pub(super) const IDX_LIMBS: usize = IDX_BITS / IDX_LIMB_BITS;
+
+const _: () = assert!(IDX_BITS % IDX_LIMB_BITS == 0);
+
+/// Splits a wire index into its [`IDX_LIMBS`] limbs.
+pub(super) fn idx_limbs(idx: u64) -> [u64; IDX_LIMBS] {
+    std::array::from_fn(|j| (idx >> (IDX_LIMB_BITS * j)) & ((1 << IDX_LIMB_BITS) - 1))
+}
+
 #[derive(Debug, Clone)]
-pub(super) struct GateIoTableConfig<F: PrimeField, const G: usize> {
+pub(super) struct GateIoTableConfig<F: PrimeField> {
     pub(super) internal_enable_gate: Column<Fixed>,
-    pub(super) enable_gate: Column<Advice>,
+    /// Input vector of the batch each row belongs to
+    pub(super) vector: Column<Fixed>,
+    /// Public, so that no gate of the committed netlist can be skipped
+    pub(super) enable_gate: Column<Instance>,
     pub(super) gate: Column<Advice>,
     pub(super) l_idx: Column<Advice>,
     pub(super) l_val: Column<Advice>,
@@ -18,13 +41,14 @@ pub(super) struct GateIoTableConfig<F: PrimeField, const G: usize> {
     pub(super) r_val: Column<Advice>,
     pub(super) o_idx: Column<Advice>,
     pub(super) o_val: Column<Advice>,
+    /// Limbs of `l_idx`, `r_idx` and `o_idx`
+    pub(super) idx_limbs: [[Column<Advice>; IDX_LIMBS]; 3],
 
     _marker: PhantomData<F>,
 }
 
 #[derive(Debug, Clone)]
 pub(super) struct GateIoTableAdvice {
-    pub(super) enable_gate: Column<Advice>,
     pub(super) gate: Column<Advice>,
     pub(super) l_idx: Column<Advice>,
     pub(super) l_val: Column<Advice>,
@@ -32,15 +56,67 @@ pub(super) struct GateIoTableAdvice {
     pub(super) r_val: Column<Advice>,
     pub(super) o_idx: Column<Advice>,
     pub(super) o_val: Column<Advice>,
+    pub(super) idx_limbs: [[Column<Advice>; IDX_LIMBS]; 3],
 }
 
-impl<F: PrimeField, const G: usize> GateIoTableConfig<F, G> {
-    pub(super) fn configure(meta: &mut ConstraintSystem<F>, advice: GateIoTableAdvice) -> Self {
+#[derive(Debug, Clone)]
+pub(super) struct GateIoTableInstance {
+    pub(super) enable_gate: Column<Instance>,
+}
+
+impl<F: PrimeField> GateIoTableConfig<F> {
+    pub(super) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: GateIoTableAdvice,
+        instance: GateIoTableInstance,
+    ) -> Self {
         let internal_enable_gate = meta.fixed_column();
+        let vector = meta.fixed_column();
+
+        // The netlist of every vector of a batch is copied from the first one, which alone
+        // is bound to the netlist commitment
+        for col in [advice.gate, advice.l_idx, advice.r_idx, advice.o_idx] {
+            meta.enable_equality(col);
+        }
+
+        // Keeps the packed netlist rows injective; the wire lookups alone only bound the
+        // indices by the number of wire rows
+        meta.create_gate("wire index range checks", |meta| {
+            let i_e_g = meta.query_fixed(internal_enable_gate, Rotation::cur());
+
+            let idx_cols = [advice.l_idx, advice.r_idx, advice.o_idx];
+            let constraints = idx_cols
+                .iter()
+                .zip(advice.idx_limbs.iter())
+                .flat_map(|(&idx_col, limb_cols)| {
+                    let idx = meta.query_advice(idx_col, Rotation::cur());
+                    let limbs = limb_cols
+                        .iter()
+                        .map(|&limb_col| meta.query_advice(limb_col, Rotation::cur()))
+                        .collect::<Vec<_>>();
+
+                    let recomposed = limbs
+                        .iter()
+                        .rev()
+                        .fold(Expression::Constant(F::ZERO), |acc, limb| {
+                            acc * F::from(1 << IDX_LIMB_BITS) + limb.clone()
+                        });
+
+                    std::iter::once(("recomposition", idx - recomposed)).chain(
+                        limbs
+                            .into_iter()
+                            .map(|limb| ("limb range", range_check(limb, 1 << IDX_LIMB_BITS))),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            Constraints::with_selector(i_e_g, constraints)
+        });
 
         Self {
             internal_enable_gate,
-            enable_gate: advice.enable_gate,
+            vector,
+            enable_gate: instance.enable_gate,
             gate: advice.gate,
             l_idx: advice.l_idx,
             l_val: advice.l_val,
@@ -48,21 +124,34 @@ impl<F: PrimeField, const G: usize> GateIoTableConfig<F, G> {
             r_val: advice.r_val,
             o_idx: advice.o_idx,
             o_val: advice.o_val,
+            idx_limbs: advice.idx_limbs,
             _marker: PhantomData,
         }
     }
 
-    pub(super) fn load_fixed(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+    /// Loads `num_gate_rows` rows for each of the `batch_size` input vectors.
+    pub(super) fn load_fixed(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        num_gate_rows: usize,
+        batch_size: usize,
+    ) -> Result<(), Error> {
         layouter.assign_region(
             || "load gate-i/o table fixed part",
             |mut region| {
-                for offset in 0..G {
+                for offset in 0..num_gate_rows * batch_size {
                     region.assign_fixed(
                         || format!("i_e_g[{}]", offset),
                         self.internal_enable_gate,
                         offset,
                         || Value::known(F::ONE),
                     )?;
+                    region.assign_fixed(
+                        || format!("vector[{}]", offset),
+                        self.vector,
+                        offset,
+                        || Value::known(F::from((offset / num_gate_rows) as u64)),
+                    )?;
                 }
 
                 Ok(())
@@ -70,6 +159,7 @@ impl<F: PrimeField, const G: usize> GateIoTableConfig<F, G> {
         )
     }
 
+    /// Assigns a gate-i/o row, returning the cells of the gate and of its wire indices.
     #[allow(clippy::too_many_arguments)]
     pub(super) fn assign_gate(
         &self,
@@ -81,25 +171,33 @@ impl<F: PrimeField, const G: usize> GateIoTableConfig<F, G> {
         r_val: Value<Assigned<F>>,
         o_idx: Value<Assigned<F>>,
         o_val: Value<Assigned<F>>,
-    ) -> Result<(), Error> {
+        idx_limbs: [[Value<Assigned<F>>; IDX_LIMBS]; 3],
+    ) -> Result<[ACell<F>; 4], Error> {
         layouter.assign_region(
             || "assign gate",
             |mut region| {
-                region.assign_advice(
-                    || "enable_gate",
-                    self.enable_gate,
-                    0,
-                    || Value::known(F::ONE),
-                )?;
-                region.assign_advice(|| "gate", self.gate, 0, || gate)?;
-                region.assign_advice(|| "l_idx", self.l_idx, 0, || l_idx)?;
+                let gate = region.assign_advice(|| "gate", self.gate, 0, || gate)?;
+                let l_idx = region.assign_advice(|| "l_idx", self.l_idx, 0, || l_idx)?;
                 region.assign_advice(|| "l_val", self.l_val, 0, || l_val)?;
-                region.assign_advice(|| "r_idx", self.r_idx, 0, || r_idx)?;
+                let r_idx = region.assign_advice(|| "r_idx", self.r_idx, 0, || r_idx)?;
                 region.assign_advice(|| "r_val", self.r_val, 0, || r_val)?;
-                region.assign_advice(|| "o_idx", self.o_idx, 0, || o_idx)?;
+                let o_idx = region.assign_advice(|| "o_idx", self.o_idx, 0, || o_idx)?;
                 region.assign_advice(|| "o_val", self.o_val, 0, || o_val)?;
 
-                Ok(())
+                for (limb_cols, limb_vals) in self.idx_limbs.iter().zip(idx_limbs.iter()) {
+                    for (j, (&limb_col, &limb_val)) in
+                        limb_cols.iter().zip(limb_vals.iter()).enumerate()
+                    {
+                        region.assign_advice(
+                            || format!("idx_limb[{}]", j),
+                            limb_col,
+                            0,
+                            || limb_val,
+                        )?;
+                    }
+                }
+
+                Ok([gate, l_idx, r_idx, o_idx].map(ACell))
             },
         )
     }
@@ -1,11 +1,11 @@
 use std::marker::PhantomData;
 
 use halo2_proofs::{
-    circuit::{AssignedCell, Cell, Layouter, Region, Value},
+    circuit::{Cell, Layouter, Value},
     plonk::{
         Advice, Assigned, Column, ConstraintSystem, Error,
         Expression::{self, Constant},
-        Fixed, Selector,
+        Fixed,
     },
     poly::Rotation,
 };
@@ -13,14 +13,24 @@ use halo2_proofs::{
 use halo2curves::ff::PrimeField;
 
 use super::common::*;
+use super::native::{mimc7_rounds, pack_blocks, ROWS_PER_BLOCK};
+
+/// Rounds of a block computed in each of its rows, one row per packed netlist row
+pub(super) const ROUNDS_PER_ROW: usize = 91usize.div_ceil(ROWS_PER_BLOCK);
+
+/// Rounds computed in the given row of a block; the last row computes the remainder.
+fn rounds_in_row(r: usize) -> usize {
+    ROUNDS_PER_ROW.min(91 - ROUNDS_PER_ROW * r)
+}
 
 #[derive(Debug, Clone)]
-pub(super) struct Mimc7CbcCipherConfig<F: PrimeField, const N: usize> {
-    pub(super) s: Column<Fixed>,
+pub(super) struct Mimc7CbcCipherConfig<F: PrimeField> {
+    /// Selector of the r-th row of each block; `s[0]` marks the first row
+    pub(super) s: [Column<Fixed>; ROWS_PER_BLOCK],
     pub(super) k: Column<Advice>,
     pub(super) iv: Column<Advice>,
     pub(super) x_in: Column<Advice>,
-    pub(super) x: [Column<Advice>; 92],
+    pub(super) x: [Column<Advice>; ROUNDS_PER_ROW + 1],
     pub(super) x_out: Column<Advice>,
 
     c: [F; 91],
@@ -33,15 +43,19 @@ pub(super) struct Mimc7CbcCipherParams<F: PrimeField> {
     pub(super) c: [F; 91], // round constants
 }
 
-impl<F: PrimeField, const N: usize> Mimc7CbcCipherConfig<F, N> {
+impl<F: PrimeField> Mimc7CbcCipherConfig<F> {
     pub(super) fn configure(
         meta: &mut ConstraintSystem<F>,
         params: Mimc7CbcCipherParams<F>,
     ) -> Self {
-        let s = meta.fixed_column();
+        let s: [Column<Fixed>; ROWS_PER_BLOCK] = (0..ROWS_PER_BLOCK)
+            .map(|_| meta.fixed_column())
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
         let k = meta.advice_column();
         let iv = meta.advice_column();
-        let x: [Column<Advice>; 92] = (0..92)
+        let x: [Column<Advice>; ROUNDS_PER_ROW + 1] = (0..=ROUNDS_PER_ROW)
             .map(|_| meta.advice_column())
             .collect::<Vec<_>>()
             .try_into()
@@ -53,9 +67,8 @@ impl<F: PrimeField, const N: usize> Mimc7CbcCipherConfig<F, N> {
         meta.enable_equality(x_out);
 
         meta.create_gate("MiMC7 CBC encryption round function", |meta| {
-            let s_ = meta.query_fixed(s, Rotation::cur());
             let k_ = meta.query_advice(k, Rotation::cur());
-            let x_ = (0..92)
+            let x_ = (0..=ROUNDS_PER_ROW)
                 .map(|i| meta.query_advice(x[i], Rotation::cur()))
                 .collect::<Vec<Expression<F>>>();
 
@@ -67,20 +80,36 @@ impl<F: PrimeField, const N: usize> Mimc7CbcCipherConfig<F, N> {
                 a4 * a2 * a
             };
 
-            (0..91)
-                .map(|i| {
-                    let s__ = s_.clone();
-                    let k__ = k_.clone();
-                    let x_i = x_[i].clone();
-                    let x_ip1 = x_[i + 1].clone();
-
-                    s__ * (pow7(x_i + Constant(c[i]) + k__) - x_ip1)
+            (0..ROWS_PER_BLOCK)
+                .flat_map(|r| {
+                    let s_r = meta.query_fixed(s[r], Rotation::cur());
+                    let k_ = k_.clone();
+                    let x_ = x_.clone();
+
+                    (0..rounds_in_row(r)).map(move |i| {
+                        let c_i = c[ROUNDS_PER_ROW * r + i];
+                        s_r.clone()
+                            * (pow7(x_[i].clone() + Constant(c_i) + k_.clone()) - x_[i + 1].clone())
+                    })
                 })
                 .collect::<Vec<_>>()
         });
 
+        meta.create_gate("MiMC7 CBC encryption round chaining", |meta| {
+            let s_ = (0..ROWS_PER_BLOCK - 1)
+                .map(|r| meta.query_fixed(s[r], Rotation::cur()))
+                .reduce(|acc, s_r| acc + s_r)
+                .unwrap();
+            let k_ = meta.query_advice(k, Rotation::cur());
+            let k_next_ = meta.query_advice(k, Rotation::next());
+            let x_last_ = meta.query_advice(x[ROUNDS_PER_ROW], Rotation::cur());
+            let x_0_next_ = meta.query_advice(x[0], Rotation::next());
+
+            vec![s_.clone() * (x_0_next_ - x_last_), s_ * (k_next_ - k_)]
+        });
+
         meta.create_gate("MiMC7 CBC encryption cipher input", |meta| {
-            let s_ = meta.query_fixed(s, Rotation::cur());
+            let s_ = meta.query_fixed(s[0], Rotation::cur());
             let iv_ = meta.query_advice(iv, Rotation::cur());
             let x_in_ = meta.query_advice(params.x_in, Rotation::cur());
             let x_0_ = meta.query_advice(x[0], Rotation::cur());
@@ -89,9 +118,10 @@ impl<F: PrimeField, const N: usize> Mimc7CbcCipherConfig<F, N> {
         });
 
         meta.create_gate("MiMC7 CBC encryption cipher output", |meta| {
-            let s_ = meta.query_fixed(s, Rotation::cur());
+            let r = ROWS_PER_BLOCK - 1;
+            let s_ = meta.query_fixed(s[r], Rotation::cur());
             let k_ = meta.query_advice(k, Rotation::cur());
-            let x_91_ = meta.query_advice(x[91], Rotation::cur());
+            let x_91_ = meta.query_advice(x[rounds_in_row(r)], Rotation::cur());
             let x_out_ = meta.query_advice(x_out, Rotation::cur());
 
             vec![s_ * (x_91_ + k_ - x_out_)]
@@ -112,68 +142,73 @@ impl<F: PrimeField, const N: usize> Mimc7CbcCipherConfig<F, N> {
     pub(super) fn synthesize(
         &self,
         mut layouter: impl Layouter<F>,
-        x_in_quarter_vals: Vec<F>,
+        x_in_row_vals: Vec<F>,
+        num_rows: usize,
         k_val: F,
         k_cell: Cell,
-    ) -> Result<(), Error> {
-        assert!(x_in_quarter_vals.len() <= N);
-        assert!(N % 4 == 0);
-
-        let mut x_in_quarter_vals = x_in_quarter_vals;
-        x_in_quarter_vals.extend((0..(N - x_in_quarter_vals.len())).map(|_| F::ZERO));
-
-        let x_in_vals = x_in_quarter_vals
-            .chunks(4)
-            .map(|l| {
-                ((l[3] * F::from(1 << 63u64) + l[2]) * F::from(1 << 63u64) + l[1])
-                    * F::from(1 << 63u64)
-                    + l[0]
-            })
-            .collect::<Vec<F>>();
+    ) -> Result<Vec<ACell<F>>, Error> {
+        let x_in_vals = pack_blocks(x_in_row_vals, num_rows);
 
         let va = |v: F| Value::known(Assigned::from(v));
 
         let mut iv_val = F::ZERO;
-        let mut prev_k_acell: Option<ACell<F>> = None;
         let mut prev_x_out_acell: Option<ACell<F>> = None;
+        let mut x_out_acells = Vec::with_capacity(x_in_vals.len());
 
-        for (row, x_in_val) in x_in_vals.iter().enumerate() {
-            let mut x_vals = [F::ZERO; 92];
-            x_vals[0] = *x_in_val + iv_val;
-
-            for i in 0..91 {
-                x_vals[i + 1] = (x_vals[i] + self.c[i] + k_val).pow([7u64]);
-            }
-
+        for (block, x_in_val) in x_in_vals.iter().enumerate() {
+            let x_vals = mimc7_rounds(*x_in_val + iv_val, k_val, &self.c);
             let x_out_val = x_vals[91] + k_val;
 
-            (prev_k_acell, prev_x_out_acell) = layouter.assign_region(
-                || format!("MiMC7 CBC assignment for row {}", row),
+            let x_out_acell = layouter.assign_region(
+                || format!("MiMC7 CBC assignment for block {}", block),
                 |mut region| {
-                    region.assign_fixed(
-                        || "MiMC7 selector input encoding",
-                        self.s,
-                        0,
-                        || Value::known(F::ONE),
-                    )?;
-
-                    let k_acell = prev_k_acell.clone().map_or(
-                        Some(
-                            region
-                                .assign_advice(|| "k", self.k, 0, || va(k_val))
-                                .map(ACell)?,
-                        ),
-                        |prev_k_acell_val| {
-                            prev_k_acell_val
-                                .0
-                                .copy_advice(|| "k", &mut region, self.k, 0)
-                                .map(ACell)
-                                .ok()
-                        },
-                    );
-
-                    if row == 0 {
-                        region.constrain_equal(k_acell.clone().unwrap().0.cell(), k_cell)?;
+                    for r in 0..ROWS_PER_BLOCK {
+                        region.assign_fixed(
+                            || "MiMC7 selector row encoding",
+                            self.s[r],
+                            r,
+                            || Value::known(F::ONE),
+                        )?;
+                        for (r_other, s_other) in self.s.iter().enumerate() {
+                            if r_other != r {
+                                region.assign_fixed(
+                                    || "MiMC7 selector row encoding",
+                                    *s_other,
+                                    r,
+                                    || Value::known(F::ZERO),
+                                )?;
+                            }
+                        }
+
+                        let k_acell = region.assign_advice(|| "k", self.k, r, || va(k_val))?;
+                        // The chaining gate carries k through the block
+                        if r == 0 {
+                            region.constrain_equal(k_acell.cell(), k_cell)?;
+                        }
+
+                        // x_i of this row is round ROUNDS_PER_ROW * r + i of the block
+                        let first_round = ROUNDS_PER_ROW * r;
+                        for i in 0..=ROUNDS_PER_ROW {
+                            let x_val = if i <= rounds_in_row(r) {
+                                x_vals[first_round + i]
+                            } else {
+                                F::ZERO
+                            };
+                            region.assign_advice(
+                                || format!("x_{}", first_round + i),
+                                self.x[i],
+                                r,
+                                || va(x_val),
+                            )?;
+                        }
+
+                        if r > 0 {
+                            region.assign_advice(|| "iv", self.iv, r, || va(F::ZERO))?;
+                            region.assign_advice(|| "x_in", self.x_in, r, || va(F::ZERO))?;
+                        }
+                        if r < ROWS_PER_BLOCK - 1 {
+                            region.assign_advice(|| "x_out", self.x_out, r, || va(F::ZERO))?;
+                        }
                     }
 
                     match prev_x_out_acell.clone() {
@@ -194,82 +229,18 @@ impl<F: PrimeField, const N: usize> Mimc7CbcCipherConfig<F, N> {
 
                     region.assign_advice(|| "x_in", self.x_in, 0, || va(*x_in_val))?;
 
-                    for i in 0..92 {
-                        region.assign_advice(
-                            || format!("x_{}", i),
-                            self.x[i],
-                            0,
-                            || va(x_vals[i]),
-                        )?;
-                    }
-
-                    let x_out_acell = Some(
-                        region
-                            .assign_advice(|| "x_out", self.x_out, 0, || va(x_out_val))
-                            .map(ACell)?,
-                    );
-
-                    Ok((k_acell, x_out_acell))
+                    region
+                        .assign_advice(|| "x_out", self.x_out, ROWS_PER_BLOCK - 1, || va(x_out_val))
+                        .map(ACell)
                 },
             )?;
 
-            self.load_zero_row(layouter.namespace(|| "MiMC7 CBC zero row"))?;
-            self.load_zero_row(layouter.namespace(|| "MiMC7 CBC zero row"))?;
-            self.load_zero_row(layouter.namespace(|| "MiMC7 CBC zero row"))?;
-
+            prev_x_out_acell = Some(x_out_acell.clone());
+            x_out_acells.push(x_out_acell);
             iv_val = x_out_val;
         }
 
-        Ok(())
-    }
-
-    fn load_zero_row(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
-        layouter.assign_region(
-            || "MiMC7 CBC assignment zero row",
-            |mut region| {
-                region.assign_fixed(
-                    || "MiMC7 selector input encoding",
-                    self.s,
-                    0,
-                    || Value::known(F::ZERO),
-                )?;
-
-                region.assign_advice(
-                    || "k",
-                    self.k,
-                    0,
-                    || Value::known(Assigned::from(F::ZERO)),
-                )?;
-                region.assign_advice(
-                    || "iv",
-                    self.iv,
-                    0,
-                    || Value::known(Assigned::from(F::ZERO)),
-                )?;
-                region.assign_advice(
-                    || "x_in",
-                    self.x_in,
-                    0,
-                    || Value::known(Assigned::from(F::ZERO)),
-                )?;
-                for i in 0..92 {
-                    region.assign_advice(
-                        || format!("x_{}", i),
-                        self.x[i],
-                        0,
-                        || Value::known(Assigned::from(F::ZERO)),
-                    )?;
-                }
-                region.assign_advice(
-                    || "x_out",
-                    self.x_out,
-                    0,
-                    || Value::known(Assigned::from(F::ZERO)),
-                )?;
-
-                Ok(())
-            },
-        )
+        Ok(x_out_acells)
     }
 }
 
@@ -1,3 +1,5 @@
+use std::marker::PhantomData;
+
 use halo2_proofs::{
     circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
     plonk::{
@@ -7,16 +9,17 @@ use halo2_proofs::{
     poly::Rotation,
 };
 
-use ff::Field;
-use halo2curves::bn256::Fr as F;
+use halo2curves::bn256::Fr;
+
+use std::io::{Error as IoError, ErrorKind};
 
-use crate::boolean_circuit::BooleanCircuitInstance;
+use crate::boolean_circuit::{BooleanCircuit, BooleanCircuitAssignment, BooleanCircuitInstance};
 
 mod common;
 use common::*;
 
 mod gate_io_table;
-use gate_io_table::{GateIoTableAdvice, GateIoTableConfig};
+use gate_io_table::{idx_limbs, GateIoTableAdvice, GateIoTableConfig, GateIoTableInstance};
 
 mod gate_definition_table;
 use gate_definition_table::GateDefinitionTableConfig;
@@ -28,39 +31,72 @@ mod expected_io_table;
 use expected_io_table::{ExpectedIoTableConfig, ExpectedIoTableInstance};
 
 mod mimc7_cbc_cipher;
-use mimc7_cbc_cipher::{Mimc7CbcCipherConfig, Mimc7CbcCipherParams, Mimc7DefaultConstants};
+use mimc7_cbc_cipher::Mimc7CbcCipherConfig;
+
+mod field;
+pub use field::ZktSimField;
+
+mod poseidon;
+use poseidon::PoseidonConfig;
+
+pub mod native;
+pub use native::wire_commitment;
+use native::{commitment_message, netlist_row_vals, IDX_BITS, ROWS_PER_BLOCK, ROW_BITS};
+
+mod visibility;
+pub use visibility::{IoVisibility, Visibility};
+
+mod encryption_key;
+pub use encryption_key::EncryptionKey;
+
+mod netlist_binding;
+pub use netlist_binding::{NetlistBinding, NetlistBindingMode};
+use netlist_binding::{NetlistBindingConfig, PoseidonSpongeConfig};
 
-mod poseidon_bn256_fr;
-use poseidon_bn256_fr::{PoseidonBN256FrConfig, PoseidonBN256FrSynthesisOutput};
+mod circuit_size;
+pub use circuit_size::CircuitSize;
+
+pub mod prover;
+use prover::{Backend, ProofOptions};
+
+mod serialization;
+
+mod srs;
+
+mod transcript;
+
+mod partition;
 
 #[derive(Debug, Clone)]
-struct ZktSimConfig<const G: usize, const W: usize> {
-    gate_io_table: GateIoTableConfig<F, G>,
-    wire_assignment_table: WireAssignmentTableConfig<F, W>,
+struct ZktSimConfig<F: ZktSimField, B: NetlistBindingConfig<F>> {
+    gate_io_table: GateIoTableConfig<F>,
+    wire_assignment_table: WireAssignmentTableConfig<F>,
     gate_definition_table: GateDefinitionTableConfig<F>,
     expected_io_table: ExpectedIoTableConfig<F>,
-    mimc7_cbc_cipher: Mimc7CbcCipherConfig<F, G>,
-    poseidon_bn256_fr: PoseidonBN256FrConfig,
+    netlist_binding: B,
+    poseidon: PoseidonConfig<F>,
+    netlist_commitment: Column<Instance>,
 }
 
-impl<const G: usize, const W: usize> ZktSimConfig<G, W> {
+impl<F: ZktSimField, B: NetlistBindingConfig<F>> ZktSimConfig<F, B> {
     pub fn configure(
         meta: &mut ConstraintSystem<F>,
         gate_io_table_advice: GateIoTableAdvice,
+        gate_io_table_instance: GateIoTableInstance,
         wire_assignment_table_advice: WireAssignmentTableAdvice,
         expected_io_table_instance: ExpectedIoTableInstance,
-        mimc7_cbc_cipher_params: Mimc7CbcCipherParams<F>,
+        netlist_commitment: Column<Instance>,
     ) -> Self {
-        let gio = GateIoTableConfig::configure(meta, gate_io_table_advice);
+        let gio = GateIoTableConfig::configure(meta, gate_io_table_advice, gate_io_table_instance);
         let wa = WireAssignmentTableConfig::configure(meta, wire_assignment_table_advice);
         let gdef = GateDefinitionTableConfig::configure(meta);
         let eio = ExpectedIoTableConfig::configure(meta, expected_io_table_instance);
-        let mcc = Mimc7CbcCipherConfig::<F, G>::configure(meta, mimc7_cbc_cipher_params);
-        let psd = PoseidonBN256FrConfig::configure(meta);
+        let nb = B::configure(meta);
+        let psd = PoseidonConfig::configure(meta);
 
         meta.lookup_any("logic gates satisfaction", |meta| {
             let i_e_g = meta.query_fixed(gio.internal_enable_gate, Rotation::cur());
-            let e_g = meta.query_advice(gio.enable_gate, Rotation::cur());
+            let e_g = meta.query_instance(gio.enable_gate, Rotation::cur());
             let g = meta.query_advice(gio.gate, Rotation::cur());
             let l_val = meta.query_advice(gio.l_val, Rotation::cur());
             let r_val = meta.query_advice(gio.r_val, Rotation::cur());
@@ -81,43 +117,68 @@ impl<const G: usize, const W: usize> ZktSimConfig<G, W> {
             ]
         });
 
+        // Each gate row only reads the wires of its own input vector
         meta.lookup_any("wire assignments satisfaction L", |meta| {
             let i_e_g = meta.query_fixed(gio.internal_enable_gate, Rotation::cur());
-            let e_g = meta.query_advice(gio.enable_gate, Rotation::cur());
+            let e_g = meta.query_instance(gio.enable_gate, Rotation::cur());
+            let g_vector = meta.query_fixed(gio.vector, Rotation::cur());
             let l_idx = meta.query_advice(gio.l_idx, Rotation::cur());
             let l_val = meta.query_advice(gio.l_val, Rotation::cur());
 
             let i_e_w = meta.query_fixed(wa.internal_enable_wire, Rotation::cur());
+            let w_vector = meta.query_fixed(wa.vector, Rotation::cur());
             let idx = meta.query_fixed(wa.idx, Rotation::cur());
             let val = meta.query_advice(wa.val, Rotation::cur());
 
-            vec![(i_e_g * e_g, i_e_w), (l_idx, idx), (l_val, val)]
+            let enable = i_e_g * e_g;
+            vec![
+                (enable.clone(), i_e_w),
+                (enable * g_vector, w_vector),
+                (l_idx, idx),
+                (l_val, val),
+            ]
         });
 
         meta.lookup_any("wire assignments satisfaction R", |meta| {
             let i_e_g = meta.query_fixed(gio.internal_enable_gate, Rotation::cur());
-            let e_g = meta.query_advice(gio.enable_gate, Rotation::cur());
+            let e_g = meta.query_instance(gio.enable_gate, Rotation::cur());
+            let g_vector = meta.query_fixed(gio.vector, Rotation::cur());
             let r_idx = meta.query_advice(gio.r_idx, Rotation::cur());
             let r_val = meta.query_advice(gio.r_val, Rotation::cur());
 
             let i_e_w = meta.query_fixed(wa.internal_enable_wire, Rotation::cur());
+            let w_vector = meta.query_fixed(wa.vector, Rotation::cur());
             let idx = meta.query_fixed(wa.idx, Rotation::cur());
             let val = meta.query_advice(wa.val, Rotation::cur());
 
-            vec![(i_e_g * e_g, i_e_w), (r_idx, idx), (r_val, val)]
+            let enable = i_e_g * e_g;
+            vec![
+                (enable.clone(), i_e_w),
+                (enable * g_vector, w_vector),
+                (r_idx, idx),
+                (r_val, val),
+            ]
         });
 
         meta.lookup_any("wire assignments satisfaction O", |meta| {
             let i_e_g = meta.query_fixed(gio.internal_enable_gate, Rotation::cur());
-            let e_g = meta.query_advice(gio.enable_gate, Rotation::cur());
+            let e_g = meta.query_instance(gio.enable_gate, Rotation::cur());
+            let g_vector = meta.query_fixed(gio.vector, Rotation::cur());
             let o_idx = meta.query_advice(gio.o_idx, Rotation::cur());
             let o_val = meta.query_advice(gio.o_val, Rotation::cur());
 
             let i_e_w = meta.query_fixed(wa.internal_enable_wire, Rotation::cur());
+            let w_vector = meta.query_fixed(wa.vector, Rotation::cur());
             let idx = meta.query_fixed(wa.idx, Rotation::cur());
             let val = meta.query_advice(wa.val, Rotation::cur());
 
-            vec![(i_e_g * e_g, i_e_w), (o_idx, idx), (o_val, val)]
+            let enable = i_e_g * e_g;
+            vec![
+                (enable.clone(), i_e_w),
+                (enable * g_vector, w_vector),
+                (o_idx, idx),
+                (o_val, val),
+            ]
         });
 
         meta.create_gate("input/output constraints satisfaction", |meta| {
@@ -128,85 +189,302 @@ impl<const G: usize, const W: usize> ZktSimConfig<G, W> {
             vec![e_i_o * (val - i_o_val)]
         });
 
-        meta.create_gate("input encoding for circuit netlist encryption", |meta| {
-            let s = meta.query_fixed(mcc.s, Rotation::cur());
-            let x_in = meta.query_advice(mcc.x_in, Rotation::cur());
-
-            let g = meta.query_advice(gio.gate, Rotation::cur());
-            let l_idx = meta.query_advice(gio.l_idx, Rotation::cur());
-            let r_idx = meta.query_advice(gio.r_idx, Rotation::cur());
-            let o_idx = meta.query_advice(gio.o_idx, Rotation::cur());
-            let limb0 = g
-                + l_idx * F::from(1 << 3u64)
-                + r_idx * F::from(1 << 23u64)
-                + o_idx * F::from(1 << 43u64);
-
-            let gp1 = meta.query_advice(gio.gate, Rotation(1));
-            let l_idx_p1 = meta.query_advice(gio.l_idx, Rotation(1));
-            let r_idx_p1 = meta.query_advice(gio.r_idx, Rotation(1));
-            let o_idx_p1 = meta.query_advice(gio.o_idx, Rotation(1));
-            let limb1 = gp1
-                + l_idx_p1 * F::from(1 << 3u64)
-                + r_idx_p1 * F::from(1 << 23u64)
-                + o_idx_p1 * F::from(1 << 43u64);
-
-            let gp2 = meta.query_advice(gio.gate, Rotation(2));
-            let l_idx_p2 = meta.query_advice(gio.l_idx, Rotation(2));
-            let r_idx_p2 = meta.query_advice(gio.r_idx, Rotation(2));
-            let o_idx_p2 = meta.query_advice(gio.o_idx, Rotation(2));
-            let limb2 = gp2
-                + l_idx_p2 * F::from(1 << 3u64)
-                + r_idx_p2 * F::from(1 << 23u64)
-                + o_idx_p2 * F::from(1 << 43u64);
-
-            let gp3 = meta.query_advice(gio.gate, Rotation(3));
-            let l_idx_p3 = meta.query_advice(gio.l_idx, Rotation(3));
-            let r_idx_p3 = meta.query_advice(gio.r_idx, Rotation(3));
-            let o_idx_p3 = meta.query_advice(gio.o_idx, Rotation(3));
-            let limb3 = gp3
-                + l_idx_p3 * F::from(1 << 3u64)
-                + r_idx_p3 * F::from(1 << 23u64)
-                + o_idx_p3 * F::from(1 << 43u64);
-
-            vec![
-                s * (((limb3 * F::from(1 << 63u64) + limb2) * F::from(1 << 63u64) + limb1)
-                    * F::from(1 << 63u64)
-                    + limb0
-                    - x_in),
-            ]
+        let (s_i_e, x_in_col) = nb.input_encoding();
+        meta.create_gate("input encoding for circuit netlist binding", |meta| {
+            let s = meta.query_fixed(s_i_e, Rotation::cur());
+            let x_in = meta.query_advice(x_in_col, Rotation::cur());
+
+            let rows = (0..ROWS_PER_BLOCK as i32)
+                .map(|j| {
+                    let g = meta.query_advice(gio.gate, Rotation(j));
+                    let l_idx = meta.query_advice(gio.l_idx, Rotation(j));
+                    let r_idx = meta.query_advice(gio.r_idx, Rotation(j));
+                    let o_idx = meta.query_advice(gio.o_idx, Rotation(j));
+
+                    g + l_idx * F::from(1u64 << 3)
+                        + r_idx * F::from(1u64 << (3 + IDX_BITS))
+                        + o_idx * F::from(1u64 << (3 + 2 * IDX_BITS))
+                })
+                .collect::<Vec<_>>();
+            let block = rows
+                .into_iter()
+                .rev()
+                .reduce(|acc, row| acc * F::from_u128(1 << ROW_BITS) + row)
+                .unwrap();
+
+            vec![s * (block - x_in)]
         });
 
         let global_constants_column = meta.fixed_column();
         meta.enable_constant(global_constants_column);
 
+        meta.enable_equality(netlist_commitment);
+
         Self {
             gate_io_table: gio,
             wire_assignment_table: wa,
             gate_definition_table: gdef,
             expected_io_table: eio,
-            mimc7_cbc_cipher: mcc,
-            poseidon_bn256_fr: psd,
+            netlist_binding: nb,
+            poseidon: psd,
+            netlist_commitment,
         }
     }
 }
 
-#[derive(Default)]
-struct ZktSimCircuit<const G: usize, const W: usize> {
+/// Witness of a gate-i/o row. The default row is the padding row.
+#[derive(Clone, Copy, Debug, Default)]
+struct GateRow {
+    gate: u64,
+    l_idx: u64,
+    l_val: bool,
+    r_idx: u64,
+    r_val: bool,
+    o_idx: u64,
+    o_val: bool,
+}
+
+struct ZktSimCircuit<F: ZktSimField, B: NetlistBindingConfig<F>> {
+    size: CircuitSize,
+    /// Committed input wires and committed output wires, which are part of the layout
+    committed_wires: [Vec<u64>; 2],
+    /// The netlist with the first input vector of the batch
     boolean_circuit_instance: BooleanCircuitInstance,
-    encryption_key: F,
+    /// Wire values of the other input vectors of the batch
+    batch_assignments: Vec<BooleanCircuitAssignment>,
+    commitment_salt: F,
+    /// Encryption key or salt of the netlist binding
+    netlist_secret: F,
+    _marker: PhantomData<B>,
+}
+
+impl<F: ZktSimField, B: NetlistBindingConfig<F>> ZktSimCircuit<F, B> {
+    fn new(
+        size: CircuitSize,
+        boolean_circuit_instance: BooleanCircuitInstance,
+        visibility: &IoVisibility<F>,
+        netlist_secret: F,
+    ) -> Self {
+        Self {
+            size,
+            committed_wires: visibility.committed_wires(&boolean_circuit_instance.ckt),
+            boolean_circuit_instance,
+            batch_assignments: Vec::new(),
+            commitment_salt: visibility.commitment_salt,
+            netlist_secret,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Circuit without witnesses, all wires zero, for key generation.
+    fn with_shape(size: CircuitSize, committed_wires: [Vec<u64>; 2]) -> Self {
+        Self {
+            committed_wires,
+            ..Self::new(size, Default::default(), &Default::default(), F::ZERO)
+        }
+    }
+
+    /// Circuit proving the evaluation of one netlist on every vector of a batch, all of
+    /// whose inputs and outputs are public.
+    fn new_batch(
+        size: CircuitSize,
+        ckt: BooleanCircuit,
+        mut assignments: Vec<BooleanCircuitAssignment>,
+        netlist_secret: F,
+    ) -> Self {
+        let batch_assignments = assignments.split_off(1.min(assignments.len()));
+        let assn = assignments.pop().unwrap_or_default();
+
+        Self {
+            batch_assignments,
+            ..Self::new(
+                size,
+                BooleanCircuitInstance { ckt, assn },
+                &IoVisibility::all_public(),
+                netlist_secret,
+            )
+        }
+    }
+
+    /// Wire values of the given vector of the batch. The vectors left unused repeat the
+    /// first one, as their gates are enabled like those of the others.
+    fn wires(&self, vector: usize) -> &[bool] {
+        let assn = match vector {
+            0 => &self.boolean_circuit_instance.assn,
+            _ => self
+                .batch_assignments
+                .get(vector - 1)
+                .unwrap_or(&self.boolean_circuit_instance.assn),
+        };
+        assn.wires.as_slice()
+    }
+
+    fn wire_val(&self, vector: usize, w: u64) -> bool {
+        self.wires(vector)
+            .get(w as usize)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Gate-i/o rows of the netlist with the values of their wires in the given vector,
+    /// padding rows included.
+    fn gate_rows(&self, vector: usize) -> Vec<GateRow> {
+        let mut gate_rows = self
+            .boolean_circuit_instance
+            .ckt
+            .gates
+            .iter()
+            .map(|gate_io| GateRow {
+                gate: gate_io.gate,
+                l_idx: gate_io.l_idx,
+                l_val: self.wire_val(vector, gate_io.l_idx),
+                r_idx: gate_io.r_idx,
+                r_val: self.wire_val(vector, gate_io.r_idx),
+                o_idx: gate_io.o_idx,
+                o_val: self.wire_val(vector, gate_io.o_idx),
+            })
+            .collect::<Vec<_>>();
+        gate_rows.resize(self.size.num_gate_rows, GateRow::default());
+        gate_rows
+    }
+
+    /// Synthesizes the circuit with the gate-i/o rows of each vector given by `gate_rows`.
+    fn synthesize_with_gate_rows(
+        &self,
+        config: ZktSimConfig<F, B>,
+        mut layouter: impl Layouter<F>,
+        gate_rows: impl Fn(usize) -> Vec<GateRow>,
+    ) -> Result<(), Error> {
+        let batch_size = self.size.batch_size;
+
+        config
+            .gate_io_table
+            .load_fixed(&mut layouter, self.size.num_gate_rows, batch_size)?;
+        config.wire_assignment_table.load_fixed(
+            &mut layouter,
+            self.size.num_wire_rows,
+            batch_size,
+        )?;
+        config.gate_definition_table.load(&mut layouter)?;
+
+        // Every wire row is assigned, so that the committed wire cells do not depend on the
+        // witness and the wires of each vector start at a fixed row
+        let mut wire_acells = Vec::with_capacity(self.size.num_wire_rows);
+        for vector in 0..batch_size {
+            for w in 0..self.size.num_wire_rows as u64 {
+                let wire_val = if self.wire_val(vector, w) {
+                    Value::known(Assigned::from(F::ONE))
+                } else {
+                    Value::known(Assigned::from(F::ZERO))
+                };
+                let acell = config
+                    .wire_assignment_table
+                    .assign_wire(layouter.namespace(|| "assign wire"), wire_val)?;
+                if vector == 0 {
+                    wire_acells.push(acell);
+                }
+            }
+        }
+        // Check if we need to explicity assign the zero wire in the last row (where internal_enable_wire is zero)?
+
+        // Gate and index cells of the first vector, which is bound to the netlist commitment.
+        // Every gate row is assigned, so that the copies of the netlist rows do not depend
+        // on the witness and the rows of each vector start at a fixed row.
+        let mut netlist_acells = Vec::with_capacity(self.size.num_gate_rows);
+        for vector in 0..batch_size {
+            for (row, gate_row) in gate_rows(vector).into_iter().enumerate() {
+                let va = |val: u64| Value::known(Assigned::from(F::from(val)));
+                let vb = |val: bool| va(val as u64);
+                let limbs = |idx: u64| idx_limbs(idx).map(va);
+
+                let acells = config.gate_io_table.assign_gate(
+                    layouter.namespace(|| "assign gate"),
+                    va(gate_row.gate),
+                    va(gate_row.l_idx),
+                    vb(gate_row.l_val),
+                    va(gate_row.r_idx),
+                    vb(gate_row.r_val),
+                    va(gate_row.o_idx),
+                    vb(gate_row.o_val),
+                    [
+                        limbs(gate_row.l_idx),
+                        limbs(gate_row.r_idx),
+                        limbs(gate_row.o_idx),
+                    ],
+                )?;
+
+                if vector == 0 {
+                    netlist_acells.push(acells);
+                    continue;
+                }
+
+                layouter.assign_region(
+                    || "copy netlist row",
+                    |mut region| {
+                        for (acell, netlist_acell) in acells.iter().zip(netlist_acells[row].iter())
+                        {
+                            region.constrain_equal(acell.0.cell(), netlist_acell.0.cell())?;
+                        }
+                        Ok(())
+                    },
+                )?;
+            }
+        }
+
+        for (row, wires) in self.committed_wires.iter().enumerate() {
+            if wires.is_empty() {
+                continue;
+            }
+
+            let msg_vals = commitment_message(
+                self.commitment_salt,
+                wires.iter().map(|&w| self.wire_val(0, w)),
+            );
+            let commit_out = config.poseidon.commit(
+                layouter.namespace(|| "Poseidon commitment of wires"),
+                &msg_vals,
+            )?;
+
+            // The first message word is the salt
+            layouter.assign_region(
+                || "bind committed wires",
+                |mut region| {
+                    for (msg_cell, &w) in commit_out.message[1..].iter().zip(wires.iter()) {
+                        region
+                            .constrain_equal(msg_cell.cell(), wire_acells[w as usize].0.cell())?;
+                    }
+                    Ok(())
+                },
+            )?;
+
+            layouter.constrain_instance(
+                commit_out.output.cell(),
+                config.expected_io_table.commitment,
+                row,
+            )?;
+        }
+
+        config.netlist_binding.synthesize(
+            &config.poseidon,
+            layouter.namespace(|| "Circuit netlist binding"),
+            netlist_row_vals(&self.boolean_circuit_instance.ckt),
+            self.size.num_gate_rows,
+            self.netlist_secret,
+            config.netlist_commitment,
+        )
+    }
 }
 
-impl<const G: usize, const W: usize> Circuit<F> for ZktSimCircuit<G, W> {
-    type Config = ZktSimConfig<G, W>;
+impl<F: ZktSimField, B: NetlistBindingConfig<F>> Circuit<F> for ZktSimCircuit<F, B> {
+    type Config = ZktSimConfig<F, B>;
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
-        Self::default()
+        Self::with_shape(self.size, self.committed_wires.clone())
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
         let gate_io_table_advice = GateIoTableAdvice {
-            enable_gate: meta.advice_column(),
             gate: meta.advice_column(),
             l_idx: meta.advice_column(),
             l_val: meta.advice_column(),
@@ -214,6 +492,10 @@ impl<const G: usize, const W: usize> Circuit<F> for ZktSimCircuit<G, W> {
             r_val: meta.advice_column(),
             o_idx: meta.advice_column(),
             o_val: meta.advice_column(),
+            idx_limbs: std::array::from_fn(|_| std::array::from_fn(|_| meta.advice_column())),
+        };
+        let gate_io_table_instance = GateIoTableInstance {
+            enable_gate: meta.instance_column(),
         };
         let wire_assignment_table_advice = WireAssignmentTableAdvice {
             val: meta.advice_column(),
@@ -221,210 +503,369 @@ impl<const G: usize, const W: usize> Circuit<F> for ZktSimCircuit<G, W> {
         let expected_io_table_instance = ExpectedIoTableInstance {
             enable_i_o: meta.instance_column(),
             i_o_val: meta.instance_column(),
+            commitment: meta.instance_column(),
         };
-        let mimc7_cbc_cipher_params = Mimc7CbcCipherParams {
-            x_in: meta.advice_column(),
-            c: Mimc7DefaultConstants(),
-        };
+        let netlist_commitment = meta.instance_column();
 
         ZktSimConfig::configure(
             meta,
             gate_io_table_advice,
+            gate_io_table_instance,
             wire_assignment_table_advice,
             expected_io_table_instance,
-            mimc7_cbc_cipher_params,
+            netlist_commitment,
         )
     }
 
-    fn synthesize(
-        &self,
-        config: Self::Config,
-        mut layouter: impl Layouter<F>,
-    ) -> Result<(), Error> {
-        config.gate_io_table.load_fixed(&mut layouter)?;
-        config.wire_assignment_table.load_fixed(&mut layouter)?;
-        config.gate_definition_table.load(&mut layouter)?;
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.synthesize_with_gate_rows(config, layouter, |vector| self.gate_rows(vector))
+    }
+}
 
-        for wire in self.boolean_circuit_instance.assn.wires.iter() {
-            let wire_val = if *wire {
-                Value::known(Assigned::from(F::ONE))
-            } else {
-                Value::known(Assigned::from(F::ZERO))
-            };
-            config
-                .wire_assignment_table
-                .assign_wire(layouter.namespace(|| "assign wire"), wire_val)?;
+/// Public values binding a proof to the circuit netlist.
+///
+/// Two proofs refer to the same committed netlist iff their netlist commitments are equal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetlistCommitment<F = Fr> {
+    Mimc7Cbc {
+        /// Poseidon hash of the encryption key
+        key_hash: F,
+        /// MiMC7 CBC encryption of the packed netlist, one block per three gates
+        ciphertext: Vec<F>,
+    },
+    PoseidonSponge {
+        /// Poseidon digest of the salt and the packed netlist
+        digest: F,
+        /// Gate capacity the netlist was padded to before hashing
+        num_gate_rows: usize,
+    },
+}
+
+impl<F: ZktSimField> NetlistCommitment<F> {
+    fn compute(ckt: &BooleanCircuit, binding: &NetlistBinding<F>, num_gate_rows: usize) -> Self {
+        match binding {
+            NetlistBinding::Mimc7Cbc(encryption_key) => Self::Mimc7Cbc {
+                key_hash: native::key_hash(encryption_key.0),
+                ciphertext: native::netlist_ciphertext(ckt, encryption_key.0, num_gate_rows),
+            },
+            NetlistBinding::PoseidonSponge { salt } => Self::PoseidonSponge {
+                digest: native::netlist_digest(ckt, *salt, num_gate_rows),
+                num_gate_rows,
+            },
         }
-        // Check if we need to explicity assign the zero wire in the last row (where internal_enable_wire is zero)?
+    }
 
-        let mut x_in_quarter_vals = Vec::<F>::new();
+    /// Checks that the key hashes to the public key hash of a MiMC7 CBC commitment.
+    pub fn matches_key(&self, encryption_key: &EncryptionKey<F>) -> bool {
+        match self {
+            Self::Mimc7Cbc { key_hash, .. } => native::key_hash(encryption_key.0) == *key_hash,
+            Self::PoseidonSponge { .. } => false,
+        }
+    }
 
-        for gate_io in self.boolean_circuit_instance.ckt.gates.iter() {
-            let va = |val: u64| Value::known(Assigned::from(F::from(val)));
-            let wire_va = |idx: u64| {
-                if self.boolean_circuit_instance.assn.wires[idx as usize] {
-                    Value::known(Assigned::from(F::ONE))
-                } else {
-                    Value::known(Assigned::from(F::ZERO))
-                }
-            };
-
-            let gate = va(gate_io.gate);
-            let l_idx = va(gate_io.l_idx);
-            let l_val = wire_va(gate_io.l_idx);
-            let r_idx = va(gate_io.r_idx);
-            let r_val = wire_va(gate_io.r_idx);
-            let o_idx = va(gate_io.o_idx);
-            let o_val = wire_va(gate_io.o_idx);
-
-            config.gate_io_table.assign_gate(
-                layouter.namespace(|| "assign gate"),
-                gate,
-                l_idx,
-                l_val,
-                r_idx,
-                r_val,
-                o_idx,
-                o_val,
-            )?;
+    /// Decrypts the committed netlist with the revealed key.
+    ///
+    /// The netlist only binds the gates, so the returned circuit has no primary inputs or
+    /// outputs. Fails if the key does not match the key hash or the ciphertext does not
+    /// decrypt to a well-formed netlist, and for sponge digests, which cannot be opened.
+    pub fn open(&self, encryption_key: &EncryptionKey<F>) -> Result<BooleanCircuit, IoError> {
+        let Self::Mimc7Cbc { ciphertext, .. } = self else {
+            return Err(IoError::new(
+                ErrorKind::Unsupported,
+                "a Poseidon sponge digest cannot be decrypted",
+            ));
+        };
 
-            let g = F::from(gate_io.gate);
-            let l_idx = F::from(gate_io.l_idx);
-            let r_idx = F::from(gate_io.r_idx);
-            let o_idx = F::from(gate_io.o_idx);
-            x_in_quarter_vals.push(
-                g + l_idx * F::from(1 << 3u64)
-                    + r_idx * F::from(1 << 23u64)
-                    + o_idx * F::from(1 << 43u64),
-            );
+        if !self.matches_key(encryption_key) {
+            Err(IoError::new(
+                ErrorKind::InvalidData,
+                "encryption key does not match the key hash",
+            ))?;
         }
 
-        let poseidon_synth_out = config.poseidon_bn256_fr.synthesize(
-            layouter.namespace(|| "Poseidon hash of encryption key"),
-            self.encryption_key,
-        )?;
+        native::open_netlist(ciphertext, encryption_key.0)
+    }
 
-        config.mimc7_cbc_cipher.synthesize(
-            layouter.namespace(|| "Circuit netlist encryption"),
-            x_in_quarter_vals,
-            self.encryption_key,
-            poseidon_synth_out.message.cell(),
-        )?;
+    /// Checks that this commitment binds the gates of `ckt`, in the same order, under the
+    /// revealed encryption key or salt.
+    pub fn is_commitment_to(&self, ckt: &BooleanCircuit, binding: &NetlistBinding<F>) -> bool {
+        match (self, binding) {
+            (Self::Mimc7Cbc { .. }, NetlistBinding::Mimc7Cbc(encryption_key)) => self
+                .open(encryption_key)
+                .is_ok_and(|opened| opened.gates == ckt.gates),
+            (
+                Self::PoseidonSponge {
+                    digest,
+                    num_gate_rows,
+                },
+                NetlistBinding::PoseidonSponge { salt },
+            ) => {
+                ckt.gates.len() <= *num_gate_rows
+                    && *digest == native::netlist_digest(ckt, *salt, *num_gate_rows)
+            }
+            _ => false,
+        }
+    }
 
-        Ok(())
+    /// Netlist commitment instance column: the key hash followed by the ciphertext, or the
+    /// sponge digest.
+    fn instance_column(&self) -> Vec<F> {
+        match self {
+            Self::Mimc7Cbc {
+                key_hash,
+                ciphertext,
+            } => std::iter::once(*key_hash)
+                .chain(ciphertext.iter().copied())
+                .collect(),
+            Self::PoseidonSponge { digest, .. } => vec![*digest],
+        }
     }
 }
 
-pub fn run_mock_prover(ckt: BooleanCircuitInstance) {
-    use halo2_proofs::dev::MockProver;
+/// Public inputs of a zktsim proof, from which the verifier builds the instance columns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicInputs<F = Fr> {
+    /// Number of gates of the netlist. With the gate definition and wire assignment
+    /// lookups, each enabled gate row holds a gate satisfied by the wire values, and each
+    /// disabled row holds zeros, which pack to the netlist padding; so no gate of the
+    /// committed netlist can be skipped.
+    pub num_gates: usize,
+    /// Public input and output wires with their values
+    pub public_wires: Vec<(u64, bool)>,
+    /// Commitments to the committed inputs and to the committed outputs, zero if there
+    /// are none
+    pub wire_commitments: [F; 2],
+    pub netlist_commitment: NetlistCommitment<F>,
+}
 
-    #[allow(non_upper_case_globals)]
-    const k: u32 = 12;
-    const G: usize = 1 << (k - 1);
-    const W: usize = 1 << (k - 1);
+impl<F: ZktSimField> PublicInputs<F> {
+    /// Public inputs of the given instance, leaving private and committed ports out.
+    pub fn new(
+        inst: &BooleanCircuitInstance,
+        visibility: &IoVisibility<F>,
+        netlist_commitment: NetlistCommitment<F>,
+    ) -> Self {
+        let bckt = &inst.ckt;
+        let wires = &inst.assn.wires;
+
+        let inputs = bckt.inputs.iter().zip(visibility.of_inputs(bckt));
+        let outputs = bckt.outputs.iter().zip(visibility.of_outputs(bckt));
+        let public_wires = inputs
+            .chain(outputs)
+            .filter(|(_, vis)| *vis == Visibility::Public)
+            .map(|(&w, _)| (w, wires[w as usize]))
+            .collect();
+
+        let wire_commitments = visibility.committed_wires(bckt).map(|committed| {
+            if committed.is_empty() {
+                F::ZERO
+            } else {
+                let values = committed
+                    .iter()
+                    .map(|&w| wires[w as usize])
+                    .collect::<Vec<_>>();
+                wire_commitment(visibility.commitment_salt, &values)
+            }
+        });
 
-    let circuit = ZktSimCircuit::<G, W> {
-        boolean_circuit_instance: ckt,
-        encryption_key: F::from(1337u64),
-    };
+        Self {
+            num_gates: bckt.gates.len(),
+            public_wires,
+            wire_commitments,
+            netlist_commitment,
+        }
+    }
 
-    let bckt = &circuit.boolean_circuit_instance.ckt;
-    let bckt_assn = &circuit.boolean_circuit_instance.assn;
+    /// All instance columns, in the order they are configured: the enable gate column,
+    /// the expected input/output columns and the netlist commitment column.
+    fn instance_columns(&self) -> Vec<Vec<F>> {
+        let inst_enable_gate = vec![F::ONE; self.num_gates];
+
+        let num_rows = self
+            .public_wires
+            .iter()
+            .map(|&(w, _)| w as usize + 1)
+            .max()
+            .unwrap_or(0);
+        let mut inst_enable_i_o = vec![F::ZERO; num_rows];
+        let mut inst_i_o_val = inst_enable_i_o.clone();
+        for &(w, val) in self.public_wires.iter() {
+            inst_enable_i_o[w as usize] = F::ONE;
+            inst_i_o_val[w as usize] = F::from(val as u64);
+        }
 
-    let mut inst_enable_i_o = vec![F::zero(); bckt_assn.wires.len()];
-    let mut inst_i_o_val = inst_enable_i_o.clone();
+        vec![
+            inst_enable_gate,
+            inst_enable_i_o,
+            inst_i_o_val,
+            self.wire_commitments.to_vec(),
+            self.netlist_commitment.instance_column(),
+        ]
+    }
+}
 
-    bckt.inputs.iter().for_each(|&i| {
-        inst_enable_i_o[i as usize] = F::one();
-        inst_i_o_val[i as usize] = F::from(bckt_assn.wires[i as usize]);
-    });
-    bckt.outputs.iter().for_each(|&o| {
-        inst_enable_i_o[o as usize] = F::one();
-        inst_i_o_val[o as usize] = F::from(bckt_assn.wires[o as usize]);
-    });
+/// Public inputs of a batch proof: the inputs and outputs of every vector are public.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchPublicInputs<F = Fr> {
+    /// Number of gates of the netlist, enabled in the gate-i/o rows of every vector
+    pub num_gates: usize,
+    /// Input and output wires of each vector with their values
+    pub vectors: Vec<Vec<(u64, bool)>>,
+    pub netlist_commitment: NetlistCommitment<F>,
+}
 
-    let instance = vec![inst_enable_i_o, inst_i_o_val];
+impl<F: ZktSimField> BatchPublicInputs<F> {
+    /// Public inputs of the evaluations of the netlist on the given assignments.
+    pub fn new(
+        ckt: &BooleanCircuit,
+        assignments: &[BooleanCircuitAssignment],
+        netlist_commitment: NetlistCommitment<F>,
+    ) -> Self {
+        let vectors = assignments
+            .iter()
+            .map(|assn| {
+                ckt.inputs
+                    .iter()
+                    .chain(ckt.outputs.iter())
+                    .map(|&w| (w, assn.wires[w as usize]))
+                    .collect()
+            })
+            .collect();
 
-    let prover = MockProver::run(k, &circuit, instance).unwrap();
-    prover.assert_satisfied();
+        Self {
+            num_gates: ckt.gates.len(),
+            vectors,
+            netlist_commitment,
+        }
+    }
+
+    /// All instance columns, in the order they are configured, with the rows of each
+    /// vector at the offsets of its tables in a circuit of the given size.
+    ///
+    /// The gates of every copy of the netlist are enabled, those of the copies left unused
+    /// included, since the copies hold the netlist rows; only the inputs and outputs of
+    /// the given vectors are.
+    fn instance_columns(&self, size: &CircuitSize) -> Vec<Vec<F>> {
+        let mut inst_enable_gate = vec![];
+        let mut inst_enable_i_o = vec![];
+        let mut inst_i_o_val = vec![];
+
+        for vector in 0..size.batch_size {
+            inst_enable_gate.resize(vector * size.num_gate_rows, F::ZERO);
+            inst_enable_gate.resize(vector * size.num_gate_rows + self.num_gates, F::ONE);
+        }
+
+        for (vector, wires) in self.vectors.iter().enumerate() {
+            for &(w, val) in wires.iter() {
+                let row = vector * size.num_wire_rows + w as usize;
+                if row >= inst_enable_i_o.len() {
+                    inst_enable_i_o.resize(row + 1, F::ZERO);
+                    inst_i_o_val.resize(row + 1, F::ZERO);
+                }
+                inst_enable_i_o[row] = F::ONE;
+                inst_i_o_val[row] = F::from(val as u64);
+            }
+        }
+
+        vec![
+            inst_enable_gate,
+            inst_enable_i_o,
+            inst_i_o_val,
+            vec![F::ZERO; 2],
+            self.netlist_commitment.instance_column(),
+        ]
+    }
 }
 
-pub fn run_prover_kzg(ckt: BooleanCircuitInstance) {
-    use halo2_proofs::{
-        plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, ProvingKey},
-        poly::{
-            kzg::{
-                commitment::{KZGCommitmentScheme, ParamsKZG},
-                multiopen::{ProverGWC, VerifierGWC},
-                strategy::SingleStrategy,
-            },
-            Rotation,
-        },
-        transcript::{
-            Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
-        },
-        SerdeFormat,
-    };
-    use halo2curves::bn256::{Bn256, G1Affine};
-    use rand_core::OsRng;
+/// Panics if the circuit does not fit in `size`, see [`CircuitSize::fits`].
+pub fn run_mock_prover<F: ZktSimField>(
+    ckt: BooleanCircuitInstance,
+    visibility: &IoVisibility<F>,
+    binding: &NetlistBinding<F>,
+    size: &CircuitSize,
+) -> NetlistCommitment<F> {
+    assert!(
+        size.fits(&ckt.ckt, visibility, binding),
+        "circuit does not fit in {:?}",
+        size
+    );
 
-    use std::time::Instant;
+    match binding {
+        NetlistBinding::Mimc7Cbc(_) => {
+            run_mock_prover_with::<Mimc7CbcCipherConfig<F>>(ckt, visibility, binding, size)
+        }
+        NetlistBinding::PoseidonSponge { .. } => {
+            run_mock_prover_with::<PoseidonSpongeConfig>(ckt, visibility, binding, size)
+        }
+    }
+}
 
-    #[allow(non_upper_case_globals)]
-    const k: u32 = 12;
-    const G: usize = 1 << (k - 1);
-    const W: usize = 1 << (k - 1);
+fn run_mock_prover_with<F: ZktSimField, B: NetlistBindingConfig<F>>(
+    ckt: BooleanCircuitInstance,
+    visibility: &IoVisibility<F>,
+    binding: &NetlistBinding<F>,
+    size: &CircuitSize,
+) -> NetlistCommitment<F> {
+    use halo2_proofs::dev::MockProver;
 
-    let circuit = ZktSimCircuit::<G, W> {
-        boolean_circuit_instance: ckt,
-        encryption_key: F::from(1337u64),
-    };
+    let k = size.k;
+    let netlist_commitment = NetlistCommitment::compute(&ckt.ckt, binding, size.num_gate_rows);
 
-    let bckt = &circuit.boolean_circuit_instance.ckt;
-    let bckt_assn = &circuit.boolean_circuit_instance.assn;
+    let public_inputs = PublicInputs::new(&ckt, visibility, netlist_commitment.clone());
 
-    let mut inst_enable_i_o = vec![F::zero(); bckt_assn.wires.len()];
-    let mut inst_i_o_val = inst_enable_i_o.clone();
+    let circuit = ZktSimCircuit::<F, B>::new(*size, ckt, visibility, binding.secret());
 
-    bckt.inputs.iter().for_each(|&i| {
-        inst_enable_i_o[i as usize] = F::one();
-        inst_i_o_val[i as usize] = F::from(bckt_assn.wires[i as usize]);
-    });
-    bckt.outputs.iter().for_each(|&o| {
-        inst_enable_i_o[o as usize] = F::one();
-        inst_i_o_val[o as usize] = F::from(bckt_assn.wires[o as usize]);
-    });
+    let prover = MockProver::run(k, &circuit, public_inputs.instance_columns()).unwrap();
+    prover.assert_satisfied();
 
-    println!("Creating parameters...");
+    netlist_commitment
+}
 
-    let params = ParamsKZG::<Bn256>::setup(k, OsRng);
+/// Sets up insecure KZG parameters, generates the keys, then proves and verifies the
+/// circuit, printing the timings.
+///
+/// Panics if the circuit does not fit in `size`, see [`CircuitSize::fits`].
+pub fn run_prover_kzg(
+    ckt: BooleanCircuitInstance,
+    visibility: &IoVisibility,
+    binding: &NetlistBinding,
+    size: &CircuitSize,
+) -> NetlistCommitment {
+    run_prover::<prover::Params>(ckt, visibility, binding, size, &Default::default())
+}
 
-    let vk = keygen_vk(&params, &circuit).expect("vk should not fail");
-    let pk = keygen_pk(&params, vk, &circuit).expect("pk should not fail");
+/// Generates parameters of the given backend, generates the keys, then proves and verifies
+/// the circuit with the given options, printing the timings and the proof size.
+///
+/// Panics if the circuit does not fit in `size`, see [`CircuitSize::fits`].
+pub fn run_prover<P: Backend>(
+    ckt: BooleanCircuitInstance,
+    visibility: &IoVisibility<P::Field>,
+    binding: &NetlistBinding<P::Field>,
+    size: &CircuitSize,
+    options: &ProofOptions,
+) -> NetlistCommitment<P::Field> {
+    use std::time::Instant;
 
-    let instance: &[&[F]] = &[&inst_enable_i_o, &inst_i_o_val];
-    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    assert!(
+        size.fits(&ckt.ckt, visibility, binding),
+        "circuit does not fit in {:?}",
+        size
+    );
+
+    println!("Creating parameters...");
+
+    let params = P::generate(size.k);
+    let shape = prover::CircuitShape::new(&ckt.ckt, visibility, binding, *size)
+        .expect("visibility should only name ports of the circuit");
+    let pk = prover::keygen(&params, &shape).expect("keygen should not fail");
 
     println!("Generating proof...");
     let proof_start_time = Instant::now();
 
-    create_proof::<
-        KZGCommitmentScheme<Bn256>,
-        ProverGWC<'_, Bn256>,
-        Challenge255<G1Affine>,
-        _,
-        Blake2bWrite<Vec<u8>, G1Affine, Challenge255<_>>,
-        _,
-    >(
-        &params,
-        &pk,
-        &[circuit],
-        &[instance],
-        OsRng,
-        &mut transcript,
-    )
-    .expect("prover should not fail");
-    let proof = transcript.finalize();
+    let (proof, public_inputs) =
+        prover::prove_with_options(&params, &pk, ckt, visibility, binding, options)
+            .expect("prover should not fail");
 
     println!("Proof generated!");
     let proof_end_time = Instant::now();
@@ -432,21 +873,19 @@ pub fn run_prover_kzg(ckt: BooleanCircuitInstance) {
         "Proof generation time: {}ms",
         proof_end_time.duration_since(proof_start_time).as_millis()
     );
-
-    let strategy = SingleStrategy::new(&params);
-    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+    println!("Proof size: {} bytes", proof.bytes.len());
 
     println!("Verifying proof...");
     let verification_start_time = Instant::now();
 
-    assert!(verify_proof::<
-        KZGCommitmentScheme<Bn256>,
-        VerifierGWC<'_, Bn256>,
-        Challenge255<G1Affine>,
-        Blake2bRead<&[u8], G1Affine, Challenge255<G1Affine>>,
-        SingleStrategy<'_, Bn256>,
-    >(&params, pk.get_vk(), strategy, &[instance], &mut transcript)
-    .is_ok());
+    prover::verify_with_options(
+        &params,
+        &pk.verifying_key(),
+        &public_inputs,
+        &proof,
+        options,
+    )
+    .expect("proof should verify");
 
     println!("Proof verified!");
     let verification_end_time = Instant::now();
@@ -456,4 +895,340 @@ pub fn run_prover_kzg(ckt: BooleanCircuitInstance) {
             .duration_since(verification_start_time)
             .as_millis()
     );
+
+    public_inputs.netlist_commitment
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2curves::bn256::Fr as F;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::boolean_circuit::{arbitrary::arb_boolean_circuit_instance, BooleanCircuitGateIo};
+
+    // Instance columns, in the order they are configured
+    const ENABLE_GATE: usize = 0;
+    const I_O_VAL: usize = 2;
+    const NETLIST_COMMITMENT: usize = 4;
+
+    /// Honest circuit of the smallest size and its instance columns.
+    fn honest<B: NetlistBindingConfig<F>>(
+        inst: BooleanCircuitInstance,
+        visibility: IoVisibility,
+        binding: &NetlistBinding,
+    ) -> (ZktSimCircuit<F, B>, Vec<Vec<F>>) {
+        let size = CircuitSize::new(&inst.ckt, &visibility, binding);
+        let netlist_commitment = NetlistCommitment::compute(&inst.ckt, binding, size.num_gate_rows);
+        let instance = PublicInputs::new(&inst, &visibility, netlist_commitment).instance_columns();
+
+        let circuit = ZktSimCircuit::new(size, inst, &visibility, binding.secret());
+
+        (circuit, instance)
+    }
+
+    /// Honest circuit and instance columns of the 6-bit adder with all-ones inputs, to be
+    /// tampered with by each test.
+    fn cla_adder() -> (ZktSimCircuit<F, Mimc7CbcCipherConfig<F>>, Vec<Vec<F>>) {
+        let ckt = BooleanCircuit::from_netlist("examples/cla_adder_6b.zkt").unwrap();
+        let inputs = vec![true; ckt.inputs.len()];
+        let inst = BooleanCircuitInstance::from_ckt_and_inputs(ckt, &inputs);
+
+        let binding = NetlistBinding::Mimc7Cbc(EncryptionKey::random());
+        honest(inst, IoVisibility::all_public(), &binding)
+    }
+
+    fn verifies<B: NetlistBindingConfig<F>>(
+        circuit: &ZktSimCircuit<F, B>,
+        instance: Vec<Vec<F>>,
+    ) -> bool {
+        let prover = MockProver::run(circuit.size.k, circuit, instance).unwrap();
+        prover.verify().is_ok()
+    }
+
+    /// Circuit assigning the given gate-i/o rows instead of the honest witness, padding
+    /// rows and the rows of the later vectors of a batch included.
+    struct Tampered<B: NetlistBindingConfig<F>> {
+        circuit: ZktSimCircuit<F, B>,
+        gate_rows: Vec<(usize, GateRow)>,
+    }
+
+    impl<B: NetlistBindingConfig<F>> Circuit<F> for Tampered<B> {
+        type Config = ZktSimConfig<F, B>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                circuit: self.circuit.without_witnesses(),
+                gate_rows: Vec::new(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            ZktSimCircuit::<F, B>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let num_gate_rows = self.circuit.size.num_gate_rows;
+            self.circuit
+                .synthesize_with_gate_rows(config, layouter, |vector| {
+                    let mut gate_rows = self.circuit.gate_rows(vector);
+                    for &(row, gate_row) in self.gate_rows.iter() {
+                        if row / num_gate_rows == vector {
+                            gate_rows[row % num_gate_rows] = gate_row;
+                        }
+                    }
+                    gate_rows
+                })
+        }
+    }
+
+    fn tampered_verifies<B: NetlistBindingConfig<F>>(
+        circuit: ZktSimCircuit<F, B>,
+        gate_rows: Vec<(usize, GateRow)>,
+        instance: Vec<Vec<F>>,
+    ) -> bool {
+        let k = circuit.size.k;
+        let prover = MockProver::run(k, &Tampered { circuit, gate_rows }, instance).unwrap();
+        prover.verify().is_ok()
+    }
+
+    fn gate_output(gate: u64, l: bool, r: bool) -> bool {
+        let ckt = BooleanCircuit {
+            inputs: vec![0, 1],
+            gates: vec![BooleanCircuitGateIo {
+                gate,
+                l_idx: 0,
+                r_idx: 1,
+                o_idx: 2,
+            }],
+            max_wire_idx: 2,
+            ..Default::default()
+        };
+        ckt.eval(&[l, r]).wires[2]
+    }
+
+    #[test]
+    fn honest_witness_verifies() {
+        let (circuit, instance) = cla_adder();
+        assert!(verifies(&circuit, instance.clone()));
+
+        // Padding rows are assigned the default row
+        let num_gates = circuit.boolean_circuit_instance.ckt.gates.len();
+        let padding = vec![(num_gates, GateRow::default())];
+        assert!(tampered_verifies(circuit, padding, instance));
+    }
+
+    #[test]
+    fn gates_cannot_be_disabled() {
+        let (mut circuit, mut instance) = cla_adder();
+
+        // Disabling a gate of the committed netlist is rejected, even if the verifier
+        // were to accept a lower gate count
+        instance[ENABLE_GATE][0] = F::zero();
+        assert!(!verifies(&circuit, instance.clone()));
+
+        // So is disabling a gate to hide a wrong output
+        let o_idx = circuit.boolean_circuit_instance.ckt.gates[0].o_idx as usize;
+        let wires = &mut circuit.boolean_circuit_instance.assn.wires;
+        wires[o_idx] = !wires[o_idx];
+        assert!(!verifies(&circuit, instance));
+    }
+
+    #[test]
+    fn flipped_wire_values_are_rejected() {
+        let (circuit, _) = cla_adder();
+        let ckt = &circuit.boolean_circuit_instance.ckt;
+        let wires = [ckt.inputs[0], ckt.gates[0].o_idx, ckt.outputs[0]];
+
+        for w in wires {
+            let (mut circuit, instance) = cla_adder();
+            let wires = &mut circuit.boolean_circuit_instance.assn.wires;
+            wires[w as usize] = !wires[w as usize];
+            assert!(!verifies(&circuit, instance), "flipped wire {}", w);
+        }
+    }
+
+    #[test]
+    fn wrong_gate_types_are_rejected() {
+        let (mut circuit, instance) = cla_adder();
+        let row = circuit.gate_rows(0)[0];
+
+        // A gate type agreeing with the wire values passes the gate lookup, so only the
+        // netlist binding catches it
+        let gate = (2..=7)
+            .find(|&g| g != row.gate && gate_output(g, row.l_val, row.r_val) == row.o_val)
+            .unwrap();
+        circuit.boolean_circuit_instance.ckt.gates[0].gate = gate;
+        assert!(!verifies(&circuit, instance));
+    }
+
+    #[test]
+    fn mismatched_indices_are_rejected() {
+        let (mut circuit, instance) = cla_adder();
+        let row = circuit.gate_rows(0)[0];
+
+        // Another wire with the same value passes the wire lookup
+        let wires = &circuit.boolean_circuit_instance.assn.wires;
+        let l_idx = (0..wires.len() as u64)
+            .find(|&w| w != row.l_idx && wires[w as usize] == row.l_val)
+            .unwrap();
+        circuit.boolean_circuit_instance.ckt.gates[0].l_idx = l_idx;
+        assert!(!verifies(&circuit, instance.clone()));
+
+        // A row value disagreeing with the wire assignment fails the wire lookup
+        let (circuit, _) = cla_adder();
+        let o_val = gate_output(row.gate, !row.l_val, row.r_val);
+        let tampered = GateRow {
+            l_val: !row.l_val,
+            o_val,
+            ..row
+        };
+        assert!(!tampered_verifies(circuit, vec![(0, tampered)], instance));
+    }
+
+    #[test]
+    fn wrong_outputs_are_rejected() {
+        let (circuit, mut instance) = cla_adder();
+        let w = circuit.boolean_circuit_instance.ckt.outputs[0] as usize;
+
+        instance[I_O_VAL][w] = F::one() - instance[I_O_VAL][w];
+        assert!(!verifies(&circuit, instance));
+    }
+
+    #[test]
+    fn altered_ciphertext_is_rejected() {
+        let (circuit, instance) = cla_adder();
+        let num_rows = instance[NETLIST_COMMITMENT].len();
+
+        // The key hash, the first and the last cipher block
+        for row in [0, 1, num_rows - 1] {
+            let mut tampered = instance.clone();
+            tampered[NETLIST_COMMITMENT][row] += F::one();
+            assert!(!verifies(&circuit, tampered), "altered row {}", row);
+        }
+    }
+
+    #[test]
+    fn tampered_padding_rows_are_rejected() {
+        let (circuit, instance) = cla_adder();
+        let num_gates = circuit.boolean_circuit_instance.ckt.gates.len();
+
+        // A satisfied gate smuggled into the first padding row
+        let row = circuit.gate_rows(0)[0];
+        assert!(!tampered_verifies(
+            circuit,
+            vec![(num_gates, row)],
+            instance
+        ));
+    }
+
+    /// Batch circuit evaluating the 6-bit adder on `num_vectors` input vectors out of
+    /// `batch_size`, and its instance columns.
+    fn cla_adder_batch(
+        num_vectors: usize,
+        batch_size: usize,
+    ) -> (ZktSimCircuit<F, PoseidonSpongeConfig>, Vec<Vec<F>>) {
+        let ckt = BooleanCircuit::from_netlist("examples/cla_adder_6b.zkt").unwrap();
+        let binding = NetlistBinding::PoseidonSponge { salt: F::from(3) };
+        let size = CircuitSize::new_batch(&ckt, &binding, batch_size);
+
+        let assignments = (0..num_vectors)
+            .map(|v| {
+                let inputs = (0..ckt.inputs.len())
+                    .map(|i| (v >> (i % 4)) & 1 == 1)
+                    .collect::<Vec<_>>();
+                ckt.eval(&inputs)
+            })
+            .collect::<Vec<_>>();
+
+        let netlist_commitment = NetlistCommitment::compute(&ckt, &binding, size.num_gate_rows);
+        let instance =
+            BatchPublicInputs::new(&ckt, &assignments, netlist_commitment).instance_columns(&size);
+        let circuit = ZktSimCircuit::new_batch(size, ckt, assignments, binding.secret());
+
+        (circuit, instance)
+    }
+
+    #[test]
+    fn batches_verify() {
+        for (num_vectors, batch_size) in [(3, 3), (3, 4), (1, 1)] {
+            let (circuit, instance) = cla_adder_batch(num_vectors, batch_size);
+            assert!(
+                verifies(&circuit, instance),
+                "{} vectors out of {}",
+                num_vectors,
+                batch_size
+            );
+        }
+
+        // The unused copy holds the netlist rows too, so its gates cannot be disabled
+        let (circuit, mut instance) = cla_adder_batch(3, 4);
+        instance[ENABLE_GATE][3 * circuit.size.num_gate_rows] = F::zero();
+        assert!(!verifies(&circuit, instance));
+    }
+
+    #[test]
+    fn each_vector_of_a_batch_is_checked() {
+        let (circuit, instance) = cla_adder_batch(3, 3);
+        let ckt = &circuit.boolean_circuit_instance.ckt;
+        let size = circuit.size;
+
+        for w in [ckt.inputs[0], ckt.outputs[0]] {
+            let row = 2 * size.num_wire_rows + w as usize;
+            let mut tampered = instance.clone();
+            tampered[I_O_VAL][row] = F::one() - tampered[I_O_VAL][row];
+            assert!(!verifies(&circuit, tampered), "flipped wire {}", w);
+        }
+
+        // The gates of every vector are enabled
+        let mut tampered = instance.clone();
+        tampered[ENABLE_GATE][size.num_gate_rows] = F::zero();
+        assert!(!verifies(&circuit, tampered));
+
+        // Only the first vector is bound to the netlist commitment, the others copy it
+        let (circuit, _) = cla_adder_batch(3, 3);
+        let row = circuit.gate_rows(1)[0];
+        let gate = (2..=7)
+            .find(|&g| g != row.gate && gate_output(g, row.l_val, row.r_val) == row.o_val)
+            .unwrap();
+        let copy = vec![(size.num_gate_rows, GateRow { gate, ..row })];
+        assert!(!tampered_verifies(circuit, copy, instance));
+    }
+
+    fn arb_visibility() -> impl Strategy<Value = IoVisibility> {
+        prop_oneof![
+            Just(IoVisibility::all_public()),
+            Just(IoVisibility::private_inputs(&["in"])),
+            any::<u64>().prop_map(|salt| IoVisibility::committed_inputs(&["in"], F::from(salt))),
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(8))]
+
+        #[test]
+        fn random_circuits_verify(
+            inst in arb_boolean_circuit_instance(1..=8, 1..=40),
+            visibility in arb_visibility(),
+            sponge in any::<bool>(),
+            secret in any::<u64>(),
+        ) {
+            if sponge {
+                let binding = NetlistBinding::PoseidonSponge { salt: F::from(secret) };
+                let (circuit, instance) = honest::<PoseidonSpongeConfig>(inst, visibility, &binding);
+                prop_assert!(verifies(&circuit, instance));
+            } else {
+                let binding = NetlistBinding::Mimc7Cbc(EncryptionKey::from_field(F::from(secret)));
+                let (circuit, instance) =
+                    honest::<Mimc7CbcCipherConfig<F>>(inst, visibility, &binding);
+                prop_assert!(verifies(&circuit, instance));
+            }
+        }
+    }
 }
@@ -0,0 +1,489 @@
+//! Native (out-of-circuit) computation of the public values of a zktsim proof.
+//!
+//! Everything here mirrors the witness generation of the MiMC7 CBC and Poseidon chips, so a
+//! verifier can recompute the expected netlist commitment and wire commitments without
+//! running a prover.
+
+use std::io::{Error, ErrorKind};
+
+use halo2curves::ff::PrimeField;
+
+use crate::boolean_circuit::{BooleanCircuit, BooleanCircuitGateIo};
+use crate::gadgets::poseidon::primitives::{self as poseidon, ConstantLength, VariableLength};
+
+use super::field::{Poseidon128, ZktSimField};
+use super::mimc7_cbc_cipher::Mimc7DefaultConstants;
+use super::poseidon::{L, RATE, WIDTH};
+
+/// Bits of each wire index in a packed netlist row, i.e. at most `2^27` wires
+pub const IDX_BITS: usize = 27;
+/// Bits of a packed netlist row: the 3-bit gate type and the three wire indices
+pub(super) const ROW_BITS: usize = 3 + 3 * IDX_BITS;
+/// Netlist rows packed into each block, the most whose 252 bits stay below the modulus
+pub(super) const ROWS_PER_BLOCK: usize = 3;
+
+/// Poseidon hash of the encryption key, as exposed in the first netlist commitment row.
+pub fn key_hash<F: ZktSimField>(encryption_key: F) -> F {
+    let mut msg_arr = [F::ZERO; L];
+    msg_arr[0] = encryption_key;
+
+    poseidon::Hash::<_, Poseidon128, ConstantLength<L>, WIDTH, RATE>::init().hash(msg_arr)
+}
+
+/// Native counterpart of [`PoseidonConfig::commit`].
+///
+/// [`PoseidonConfig::commit`]: super::poseidon::PoseidonConfig::commit
+pub(super) fn poseidon_commit<F: ZktSimField>(msg_vals: &[F]) -> F {
+    poseidon::Hash::<_, Poseidon128, VariableLength, WIDTH, RATE>::init().hash(msg_vals)
+}
+
+pub(super) fn commitment_message<F: ZktSimField>(
+    salt: F,
+    values: impl Iterator<Item = bool>,
+) -> Vec<F> {
+    std::iter::once(salt)
+        .chain(values.map(|val| F::from(val as u64)))
+        .collect()
+}
+
+/// Poseidon commitment to the given wire values, as exposed for committed ports.
+pub fn wire_commitment<F: ZktSimField>(salt: F, values: &[bool]) -> F {
+    poseidon_commit(&commitment_message(salt, values.iter().copied()))
+}
+
+/// MiMC7 CBC encryption of the netlist of `ckt` padded to `num_gate_rows` gates, one block
+/// per three gates, as exposed after the key hash in the netlist commitment column.
+pub fn netlist_ciphertext<F: ZktSimField>(
+    ckt: &BooleanCircuit,
+    encryption_key: F,
+    num_gate_rows: usize,
+) -> Vec<F> {
+    let x_in_vals = pack_blocks(netlist_row_vals(ckt), num_gate_rows);
+    mimc7_cbc_encrypt(&x_in_vals, encryption_key, &Mimc7DefaultConstants())
+}
+
+/// Poseidon sponge digest of `salt` followed by the netlist of `ckt` packed as in
+/// [`netlist_ciphertext`], as exposed in the netlist commitment column.
+pub fn netlist_digest<F: ZktSimField>(ckt: &BooleanCircuit, salt: F, num_gate_rows: usize) -> F {
+    let msg_vals = std::iter::once(salt)
+        .chain(pack_blocks(netlist_row_vals(ckt), num_gate_rows))
+        .collect::<Vec<_>>();
+    poseidon_commit(&msg_vals)
+}
+
+/// Decrypts a netlist ciphertext back into its gates.
+///
+/// The netlist only binds the gates, so the returned circuit has no primary inputs or
+/// outputs. Fails if the ciphertext does not decrypt to a well-formed netlist, e.g. under
+/// the wrong key.
+pub fn open_netlist<F: ZktSimField>(
+    ciphertext: &[F],
+    encryption_key: F,
+) -> Result<BooleanCircuit, Error> {
+    let x_in_vals = mimc7_cbc_decrypt(ciphertext, encryption_key, &Mimc7DefaultConstants());
+    let x_in_row_vals = unpack_blocks(&x_in_vals)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "ciphertext is not a packed netlist"))?;
+
+    netlist_from_row_vals(&x_in_row_vals)
+}
+
+/// Packs each gate as `g + l_idx * 2^3 + r_idx * 2^30 + o_idx * 2^57`.
+///
+/// Panics if a wire index does not fit in [`IDX_BITS`] bits.
+pub(super) fn netlist_row_vals<F: PrimeField>(ckt: &BooleanCircuit) -> Vec<F> {
+    assert!(
+        ckt.max_wire_idx >> IDX_BITS == 0,
+        "wire indices must be below 2^{}",
+        IDX_BITS
+    );
+
+    ckt.gates
+        .iter()
+        .map(|gate_io| {
+            F::from(gate_io.gate)
+                + F::from(gate_io.l_idx) * F::from(1u64 << 3)
+                + F::from(gate_io.r_idx) * F::from(1u64 << (3 + IDX_BITS))
+                + F::from(gate_io.o_idx) * F::from(1u64 << (3 + 2 * IDX_BITS))
+        })
+        .collect()
+}
+
+/// Inverse of [`netlist_row_vals`]. Zero rows are padding and may only trail the gates.
+fn netlist_from_row_vals(x_in_row_vals: &[u128]) -> Result<BooleanCircuit, Error> {
+    const IDX_MASK: u128 = (1 << IDX_BITS) - 1;
+
+    let num_gates = x_in_row_vals
+        .iter()
+        .rposition(|&q| q != 0)
+        .map_or(0, |i| i + 1);
+
+    let mut gates = Vec::with_capacity(num_gates);
+    let mut max_wire_idx = 0;
+
+    for (row, &q) in x_in_row_vals[..num_gates].iter().enumerate() {
+        let gate_io = BooleanCircuitGateIo {
+            gate: (q & 0b111) as u64,
+            l_idx: ((q >> 3) & IDX_MASK) as u64,
+            r_idx: ((q >> (3 + IDX_BITS)) & IDX_MASK) as u64,
+            o_idx: ((q >> (3 + 2 * IDX_BITS)) & IDX_MASK) as u64,
+        };
+
+        if gate_io.gate == 0 {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid gate in netlist row {}", row),
+            ))?;
+        }
+
+        max_wire_idx = max_wire_idx
+            .max(gate_io.l_idx)
+            .max(gate_io.r_idx)
+            .max(gate_io.o_idx);
+        gates.push(gate_io);
+    }
+
+    Ok(BooleanCircuit {
+        gates,
+        max_wire_idx,
+        ..Default::default()
+    })
+}
+
+/// Packs [`ROWS_PER_BLOCK`] netlist rows into each cipher block, after zero-padding to
+/// `num_rows` rows.
+pub(super) fn pack_blocks<F: PrimeField>(x_in_row_vals: Vec<F>, num_rows: usize) -> Vec<F> {
+    assert!(x_in_row_vals.len() <= num_rows);
+    assert!(num_rows % ROWS_PER_BLOCK == 0);
+
+    let mut x_in_row_vals = x_in_row_vals;
+    x_in_row_vals.resize(num_rows, F::ZERO);
+
+    x_in_row_vals
+        .chunks(ROWS_PER_BLOCK)
+        .map(|l| {
+            l.iter()
+                .rev()
+                .fold(F::ZERO, |acc, &l_j| acc * F::from_u128(1 << ROW_BITS) + l_j)
+        })
+        .collect()
+}
+
+/// Splits each cipher block back into its netlist rows, the inverse of [`pack_blocks`].
+///
+/// Returns `None` if a block does not fit in [`ROWS_PER_BLOCK`] rows of [`ROW_BITS`] bits.
+/// Assumes a little-endian field representation.
+pub(super) fn unpack_blocks<F: PrimeField>(x_in_vals: &[F]) -> Option<Vec<u128>> {
+    let mut x_in_row_vals = Vec::with_capacity(ROWS_PER_BLOCK * x_in_vals.len());
+
+    for x_in_val in x_in_vals {
+        let repr = x_in_val.to_repr();
+        let bytes = repr.as_ref();
+        let bit = |i: usize| bytes.get(i / 8).map_or(0, |b| (b >> (i % 8)) & 1);
+
+        let num_bits = ROWS_PER_BLOCK * ROW_BITS;
+        if (num_bits..8 * bytes.len()).any(|i| bit(i) != 0) {
+            return None;
+        }
+
+        for j in 0..ROWS_PER_BLOCK {
+            let row =
+                (0..ROW_BITS).fold(0u128, |acc, i| acc | (bit(ROW_BITS * j + i) as u128) << i);
+            x_in_row_vals.push(row);
+        }
+    }
+
+    Some(x_in_row_vals)
+}
+
+/// Values of x_0 to x_91 for a single block encryption.
+pub(super) fn mimc7_rounds<F: PrimeField>(x_0: F, k_val: F, c: &[F; 91]) -> [F; 92] {
+    let mut x_vals = [F::ZERO; 92];
+    x_vals[0] = x_0;
+
+    for i in 0..91 {
+        x_vals[i + 1] = (x_vals[i] + c[i] + k_val).pow([7u64]);
+    }
+
+    x_vals
+}
+
+/// Native MiMC7 CBC encryption, matching the x_out values of [`Mimc7CbcCipherConfig::synthesize`].
+///
+/// [`Mimc7CbcCipherConfig::synthesize`]: super::mimc7_cbc_cipher::Mimc7CbcCipherConfig::synthesize
+pub(super) fn mimc7_cbc_encrypt<F: PrimeField>(x_in_vals: &[F], k_val: F, c: &[F; 91]) -> Vec<F> {
+    let mut iv_val = F::ZERO;
+    x_in_vals
+        .iter()
+        .map(|x_in_val| {
+            let x_vals = mimc7_rounds(*x_in_val + iv_val, k_val, c);
+            iv_val = x_vals[91] + k_val;
+            iv_val
+        })
+        .collect()
+}
+
+/// Native MiMC7 CBC decryption, the inverse of [`mimc7_cbc_encrypt`].
+pub(super) fn mimc7_cbc_decrypt<F: PrimeField>(x_out_vals: &[F], k_val: F, c: &[F; 91]) -> Vec<F> {
+    let root7 = pow7_inv_exponent::<F>();
+
+    let mut iv_val = F::ZERO;
+    x_out_vals
+        .iter()
+        .map(|x_out_val| {
+            let mut x_val = *x_out_val - k_val;
+            for i in (0..91).rev() {
+                x_val = x_val.pow(root7) - c[i] - k_val;
+            }
+
+            let x_in_val = x_val - iv_val;
+            iv_val = *x_out_val;
+            x_in_val
+        })
+        .collect()
+}
+
+/// Exponent `d` with `7 * d == 1 (mod p - 1)`, so that `x^d` inverts `x^7`.
+///
+/// Assumes a little-endian field representation, as used by the BN256 and Pasta fields.
+fn pow7_inv_exponent<F: PrimeField>() -> [u64; 5] {
+    let p_minus_1 = (-F::ONE).to_repr();
+    let mut limbs = [0u64; 5];
+    for (i, chunk) in p_minus_1.as_ref().chunks(8).enumerate() {
+        limbs[i] = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+
+    let mul_add = |a: &[u64; 5], m: u64, c: u64| {
+        let mut out = [0u64; 5];
+        let mut carry = c as u128;
+        for i in 0..5 {
+            let t = a[i] as u128 * m as u128 + carry;
+            out[i] = t as u64;
+            carry = t >> 64;
+        }
+        out
+    };
+
+    let div_rem = |a: &[u64; 5], d: u64| {
+        let mut out = [0u64; 5];
+        let mut rem = 0u128;
+        for i in (0..5).rev() {
+            let t = (rem << 64) | a[i] as u128;
+            out[i] = (t / d as u128) as u64;
+            rem = t % d as u128;
+        }
+        (out, rem)
+    };
+
+    // d = (m * (p - 1) + 1) / 7 for the m in 1..7 making the division exact
+    (1..7)
+        .map(|m| div_rem(&mul_add(&limbs, m, 1), 7))
+        .find(|(_, rem)| *rem == 0)
+        .map(|(d, _)| d)
+        .expect("x^7 is not a permutation of the field")
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::MockProver,
+        plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+    };
+    use halo2curves::{bn256::Fr, ff::FromUniformBytes, pasta::pallas::Base as Fp};
+    use proptest::prelude::*;
+
+    use super::super::{
+        mimc7_cbc_cipher::{Mimc7CbcCipherConfig, Mimc7CbcCipherParams},
+        netlist_binding::{NetlistBindingConfig, PoseidonSpongeConfig},
+        poseidon::PoseidonConfig,
+    };
+    use super::*;
+
+    const N: usize = 5 * ROWS_PER_BLOCK;
+
+    prop_compose! {
+        fn arb_fr()(bytes in prop::array::uniform32(0u8..)) -> Fr {
+            let mut buf = [0; 64];
+            buf[..32].copy_from_slice(&bytes);
+            Fr::from_uniform_bytes(&buf)
+        }
+    }
+
+    prop_compose! {
+        fn arb_fp()(bytes in prop::array::uniform32(0u8..)) -> Fp {
+            let mut buf = [0; 64];
+            buf[..32].copy_from_slice(&bytes);
+            Fp::from_uniform_bytes(&buf)
+        }
+    }
+
+    prop_compose! {
+        fn arb_gate_io()(
+            gate in 1u64..=7,
+            l_idx in 0u64..(1 << IDX_BITS),
+            r_idx in 0u64..(1 << IDX_BITS),
+            o_idx in 0u64..(1 << IDX_BITS),
+        ) -> BooleanCircuitGateIo {
+            BooleanCircuitGateIo { gate, l_idx, r_idx, o_idx }
+        }
+    }
+
+    prop_compose! {
+        fn arb_netlist()(gates in prop::collection::vec(arb_gate_io(), 0..=N)) -> BooleanCircuit {
+            BooleanCircuit { gates, ..Default::default() }
+        }
+    }
+
+    /// Runs the in-circuit key hash, wire commitment and netlist encryption, exposing the
+    /// key hash, the commitment and then the ciphertext in a single instance column. The
+    /// netlist sponge digest is exposed in a second instance column.
+    #[derive(Default)]
+    struct NativeConsistencyCircuit {
+        x_in_row_vals: Vec<Fr>,
+        encryption_key: Fr,
+        commitment_message: Vec<Fr>,
+        salt: Fr,
+    }
+
+    #[derive(Clone)]
+    struct NativeConsistencyConfig {
+        mimc7_cbc_cipher: Mimc7CbcCipherConfig<Fr>,
+        poseidon_sponge: PoseidonSpongeConfig,
+        poseidon: PoseidonConfig<Fr>,
+        public: Column<Instance>,
+        digest: Column<Instance>,
+    }
+
+    impl Circuit<Fr> for NativeConsistencyCircuit {
+        type Config = NativeConsistencyConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let mimc7_cbc_cipher_params = Mimc7CbcCipherParams {
+                x_in: meta.advice_column(),
+                c: Mimc7DefaultConstants(),
+            };
+            let public = meta.instance_column();
+            let digest = meta.instance_column();
+            meta.enable_equality(public);
+            meta.enable_equality(digest);
+
+            NativeConsistencyConfig {
+                mimc7_cbc_cipher: Mimc7CbcCipherConfig::configure(meta, mimc7_cbc_cipher_params),
+                poseidon_sponge: <PoseidonSpongeConfig as NetlistBindingConfig<Fr>>::configure(
+                    meta,
+                ),
+                poseidon: PoseidonConfig::configure(meta),
+                public,
+                digest,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let hash_out = config
+                .poseidon
+                .synthesize(layouter.namespace(|| "key hash"), self.encryption_key)?;
+            layouter.constrain_instance(hash_out.output.cell(), config.public, 0)?;
+
+            let commit_out = config.poseidon.commit(
+                layouter.namespace(|| "wire commitment"),
+                &self.commitment_message,
+            )?;
+            layouter.constrain_instance(commit_out.output.cell(), config.public, 1)?;
+
+            let x_out_acells = config.mimc7_cbc_cipher.synthesize(
+                layouter.namespace(|| "netlist encryption"),
+                self.x_in_row_vals.clone(),
+                N,
+                self.encryption_key,
+                hash_out.message.cell(),
+            )?;
+            for (i, x_out_acell) in x_out_acells.iter().enumerate() {
+                layouter.constrain_instance(x_out_acell.0.cell(), config.public, i + 2)?;
+            }
+
+            config.poseidon_sponge.synthesize(
+                &config.poseidon,
+                layouter.namespace(|| "netlist digest"),
+                self.x_in_row_vals.clone(),
+                N,
+                self.salt,
+                config.digest,
+            )
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn mimc7_cbc_decrypt_inverts_encrypt(
+            key in arb_fr(),
+            x_in_vals in prop::collection::vec(arb_fr(), 1..4),
+        ) {
+            let c = Mimc7DefaultConstants();
+            let x_out_vals = mimc7_cbc_encrypt(&x_in_vals, key, &c);
+            prop_assert_eq!(mimc7_cbc_decrypt(&x_out_vals, key, &c), x_in_vals);
+        }
+
+        #[test]
+        fn netlist_opens_to_encrypted_gates(ckt in arb_netlist(), key in arb_fr()) {
+            let ciphertext = netlist_ciphertext(&ckt, key, N);
+            prop_assert_eq!(ciphertext.len(), N / ROWS_PER_BLOCK);
+
+            let opened = open_netlist(&ciphertext, key).unwrap();
+            prop_assert_eq!(opened.gates, ckt.gates);
+        }
+
+        #[test]
+        fn netlist_opens_over_pasta(ckt in arb_netlist(), key in arb_fp()) {
+            let ciphertext = netlist_ciphertext(&ckt, key, N);
+            let opened = open_netlist(&ciphertext, key).unwrap();
+            prop_assert_eq!(opened.gates, ckt.gates);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(8))]
+
+        #[test]
+        fn native_matches_circuit(
+            ckt in arb_netlist(),
+            key in arb_fr(),
+            salt in arb_fr(),
+            values in prop::collection::vec(any::<bool>(), 1..6),
+        ) {
+            let circuit = NativeConsistencyCircuit {
+                x_in_row_vals: netlist_row_vals(&ckt),
+                encryption_key: key,
+                commitment_message: commitment_message(salt, values.iter().copied()),
+                salt,
+            };
+
+            let mut public = vec![key_hash(key), wire_commitment(salt, &values)];
+            public.extend(netlist_ciphertext(&ckt, key, N));
+            let digest = vec![netlist_digest(&ckt, salt, N)];
+
+            let prover = MockProver::run(9, &circuit, vec![public.clone(), digest.clone()]).unwrap();
+            prop_assert!(prover.verify().is_ok());
+
+            // Any deviation from the native values must be rejected
+            for row in [0, 1, 2] {
+                let mut tampered = public.clone();
+                tampered[row] += Fr::one();
+                let prover = MockProver::run(9, &circuit, vec![tampered, digest.clone()]).unwrap();
+                prop_assert!(prover.verify().is_err());
+            }
+
+            let tampered = vec![digest[0] + Fr::one()];
+            let prover = MockProver::run(9, &circuit, vec![public, tampered]).unwrap();
+            prop_assert!(prover.verify().is_err());
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt::Debug;
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Instance},
+};
+use halo2curves::bn256::Fr;
+
+use super::encryption_key::EncryptionKey;
+use super::field::ZktSimField;
+use super::mimc7_cbc_cipher::{Mimc7CbcCipherConfig, Mimc7CbcCipherParams, Mimc7DefaultConstants};
+use super::native::{pack_blocks, ROWS_PER_BLOCK};
+use super::poseidon::PoseidonConfig;
+
+/// How the gate netlist is bound to the public netlist commitment.
+#[derive(Clone, Copy, Debug)]
+pub enum NetlistBinding<F = Fr> {
+    /// MiMC7 CBC encryption of the netlist, exposed together with the Poseidon hash of the
+    /// key. The key holder can later reveal the netlist by disclosing the key.
+    Mimc7Cbc(EncryptionKey<F>),
+    /// Poseidon sponge digest of `salt` followed by the packed netlist. The digest is
+    /// collision resistant, and hiding as long as the salt is random and kept secret; a
+    /// zero salt gives a deterministic digest.
+    PoseidonSponge { salt: F },
+}
+
+/// Netlist binding without its secret, which determines the circuit layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NetlistBindingMode {
+    Mimc7Cbc,
+    PoseidonSponge,
+}
+
+impl<F: ZktSimField> NetlistBinding<F> {
+    pub fn mode(&self) -> NetlistBindingMode {
+        match self {
+            Self::Mimc7Cbc(_) => NetlistBindingMode::Mimc7Cbc,
+            Self::PoseidonSponge { .. } => NetlistBindingMode::PoseidonSponge,
+        }
+    }
+
+    /// The private witness of the binding: the encryption key or the salt.
+    pub(super) fn secret(&self) -> F {
+        match self {
+            Self::Mimc7Cbc(encryption_key) => encryption_key.0,
+            Self::PoseidonSponge { salt } => *salt,
+        }
+    }
+}
+
+/// Chip binding the packed netlist rows to the netlist commitment instance column.
+pub(super) trait NetlistBindingConfig<F: ZktSimField>: Clone + Debug {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self;
+
+    /// Selector enabled on the first gate row of every block, and the column holding the packed rows
+    /// checked against the gate-i/o table by the input encoding gate.
+    fn input_encoding(&self) -> (Column<Fixed>, Column<Advice>);
+
+    fn synthesize(
+        &self,
+        poseidon: &PoseidonConfig<F>,
+        layouter: impl Layouter<F>,
+        x_in_row_vals: Vec<F>,
+        num_gate_rows: usize,
+        secret: F,
+        netlist_commitment: Column<Instance>,
+    ) -> Result<(), Error>;
+}
+
+impl<F: ZktSimField> NetlistBindingConfig<F> for Mimc7CbcCipherConfig<F> {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let params = Mimc7CbcCipherParams {
+            x_in: meta.advice_column(),
+            c: Mimc7DefaultConstants(),
+        };
+
+        Mimc7CbcCipherConfig::configure(meta, params)
+    }
+
+    fn input_encoding(&self) -> (Column<Fixed>, Column<Advice>) {
+        (self.s[0], self.x_in)
+    }
+
+    fn synthesize(
+        &self,
+        poseidon: &PoseidonConfig<F>,
+        mut layouter: impl Layouter<F>,
+        x_in_row_vals: Vec<F>,
+        num_gate_rows: usize,
+        secret: F,
+        netlist_commitment: Column<Instance>,
+    ) -> Result<(), Error> {
+        let poseidon_synth_out = poseidon.synthesize(
+            layouter.namespace(|| "Poseidon hash of encryption key"),
+            secret,
+        )?;
+
+        layouter.constrain_instance(poseidon_synth_out.output.cell(), netlist_commitment, 0)?;
+
+        let x_out_acells = Mimc7CbcCipherConfig::synthesize(
+            self,
+            layouter.namespace(|| "Circuit netlist encryption"),
+            x_in_row_vals,
+            num_gate_rows,
+            secret,
+            poseidon_synth_out.message.cell(),
+        )?;
+
+        for (i, x_out_acell) in x_out_acells.iter().enumerate() {
+            layouter.constrain_instance(x_out_acell.0.cell(), netlist_commitment, i + 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Absorbs the salt and the packed netlist blocks into the Poseidon sponge.
+///
+/// Only needs one advice and one fixed column besides the shared Poseidon chip, at the cost
+/// of one permutation per two blocks.
+#[derive(Debug, Clone)]
+pub(super) struct PoseidonSpongeConfig {
+    s: Column<Fixed>,
+    x_in: Column<Advice>,
+}
+
+impl<F: ZktSimField> NetlistBindingConfig<F> for PoseidonSpongeConfig {
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let s = meta.fixed_column();
+        let x_in = meta.advice_column();
+
+        meta.enable_equality(x_in);
+
+        Self { s, x_in }
+    }
+
+    fn input_encoding(&self) -> (Column<Fixed>, Column<Advice>) {
+        (self.s, self.x_in)
+    }
+
+    fn synthesize(
+        &self,
+        poseidon: &PoseidonConfig<F>,
+        mut layouter: impl Layouter<F>,
+        x_in_row_vals: Vec<F>,
+        num_gate_rows: usize,
+        secret: F,
+        netlist_commitment: Column<Instance>,
+    ) -> Result<(), Error> {
+        let x_in_vals = pack_blocks(x_in_row_vals, num_gate_rows);
+
+        let x_in_cells: Vec<AssignedCell<F, F>> = layouter.assign_region(
+            || "packed netlist rows",
+            |mut region| {
+                let mut x_in_cells = Vec::with_capacity(x_in_vals.len());
+
+                for row in 0..num_gate_rows {
+                    let is_block = row % ROWS_PER_BLOCK == 0;
+                    let x_in_val = if is_block {
+                        x_in_vals[row / ROWS_PER_BLOCK]
+                    } else {
+                        F::ZERO
+                    };
+
+                    region.assign_fixed(
+                        || "packed netlist selector",
+                        self.s,
+                        row,
+                        || Value::known(if is_block { F::ONE } else { F::ZERO }),
+                    )?;
+                    let x_in_cell = region.assign_advice(
+                        || format!("x_in[{}]", row),
+                        self.x_in,
+                        row,
+                        || Value::known(x_in_val),
+                    )?;
+
+                    if is_block {
+                        x_in_cells.push(x_in_cell);
+                    }
+                }
+
+                Ok(x_in_cells)
+            },
+        )?;
+
+        let msg_vals = std::iter::once(secret)
+            .chain(x_in_vals.iter().copied())
+            .collect::<Vec<_>>();
+        let commit_out = poseidon.commit(
+            layouter.namespace(|| "Poseidon sponge over netlist"),
+            &msg_vals,
+        )?;
+
+        // The first message word is the salt
+        layouter.assign_region(
+            || "bind packed netlist rows",
+            |mut region| {
+                for (msg_cell, x_in_cell) in commit_out.message[1..].iter().zip(x_in_cells.iter()) {
+                    region.constrain_equal(msg_cell.cell(), x_in_cell.cell())?;
+                }
+                Ok(())
+            },
+        )?;
+
+        layouter.constrain_instance(commit_out.output.cell(), netlist_commitment, 0)
+    }
+}
@@ -0,0 +1,115 @@
+use std::io::{Error as IoError, ErrorKind};
+
+use crate::boolean_circuit::{BooleanCircuit, Partition, CUT_INPUT_PORT, CUT_OUTPUT_PORT};
+
+use super::circuit_size::CircuitSize;
+use super::field::ZktSimField;
+use super::native::wire_commitment;
+use super::netlist_binding::{NetlistBinding, NetlistBindingMode};
+use super::prover::CircuitShape;
+use super::visibility::{IoVisibility, Visibility};
+use super::NetlistCommitment;
+
+impl Partition {
+    /// Smallest size fitting every part, with its cut ports committed.
+    pub fn size(&self, binding: NetlistBindingMode) -> CircuitSize {
+        let max_gates = self
+            .parts
+            .iter()
+            .map(|part| part.gates.len())
+            .max()
+            .unwrap_or(0);
+        let max_wires = self
+            .parts
+            .iter()
+            .map(|part| part.max_wire_idx as usize + 1)
+            .max()
+            .unwrap_or(1);
+
+        CircuitSize::with_limits(max_gates, max_wires, self.cut_width, binding)
+    }
+
+    /// Shape of the parts, whose only committed wires are the cut wires.
+    pub fn shape(&self, binding: NetlistBindingMode) -> CircuitShape {
+        let k = self.cut_width as u64;
+        CircuitShape {
+            size: self.size(binding),
+            binding,
+            committed_wires: [(0..k).collect(), (k..2 * k).collect()],
+        }
+    }
+
+    /// Netlist commitments of the parts, in order, as the verifier expects them.
+    pub fn netlist_commitments<F: ZktSimField>(
+        &self,
+        binding: &NetlistBinding<F>,
+    ) -> Vec<NetlistCommitment<F>> {
+        let num_gate_rows = self.size(binding.mode()).num_gate_rows;
+        self.parts
+            .iter()
+            .map(|part| NetlistCommitment::compute(part, binding, num_gate_rows))
+            .collect()
+    }
+
+    /// Commitment to the all-zero cut wires the first part receives, under the
+    /// commitment salt of the visibility, or zero if there is no cut.
+    pub fn first_cut_commitment<F: ZktSimField>(&self, commitment_salt: F) -> F {
+        if self.cut_width == 0 {
+            F::ZERO
+        } else {
+            wire_commitment(commitment_salt, &vec![false; self.cut_width])
+        }
+    }
+
+    /// Visibility of the ports of the parts: that of the primary ports, with the cut ports
+    /// committed under the commitment salt of `visibility`.
+    ///
+    /// Fails if a primary port is committed, as all the parts must commit to the same
+    /// wires to share a proving key, or if an entry names no primary port.
+    pub fn visibility<F: ZktSimField>(
+        &self,
+        visibility: &IoVisibility<F>,
+    ) -> Result<IoVisibility<F>, IoError> {
+        let names = |ports: fn(&BooleanCircuit) -> &Vec<String>| {
+            self.parts
+                .iter()
+                .flat_map(|part| ports(part).iter().cloned())
+                .collect::<Vec<_>>()
+        };
+        IoVisibility::check_names(
+            &visibility.inputs,
+            &names(|part| &part.input_names),
+            "input",
+        )?;
+        IoVisibility::check_names(
+            &visibility.outputs,
+            &names(|part| &part.output_names),
+            "output",
+        )?;
+
+        if let Some(name) = visibility
+            .inputs
+            .iter()
+            .chain(visibility.outputs.iter())
+            .find(|(_, vis)| **vis == Visibility::Committed)
+            .map(|(name, _)| name)
+        {
+            return Err(IoError::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "port {} cannot be committed, the commitments hold the cut wires",
+                    name
+                ),
+            ));
+        }
+
+        let mut parts_visibility = visibility.clone();
+        parts_visibility
+            .inputs
+            .insert(CUT_INPUT_PORT.to_string(), Visibility::Committed);
+        parts_visibility
+            .outputs
+            .insert(CUT_OUTPUT_PORT.to_string(), Visibility::Committed);
+        Ok(parts_visibility)
+    }
+}
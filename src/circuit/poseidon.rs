@@ -0,0 +1,165 @@
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{
+        Advice, Assigned, Column, ConstraintSystem, Error,
+        Expression::{self, Constant},
+        Fixed, Selector,
+    },
+    poly::Rotation,
+};
+use halo2curves::bn256::Fr;
+
+use crate::gadgets::poseidon::{
+    primitives::{ConstantLength, Spec, VariableLength},
+    Hash, Pow5Chip, Pow5Config,
+};
+
+use super::field::{Poseidon128, ZktSimField};
+
+pub(super) const WIDTH: usize = 3;
+pub(super) const RATE: usize = 2;
+pub(super) const L: usize = 2;
+
+#[derive(Debug, Clone)]
+pub(super) struct PoseidonConfig<F: ZktSimField> {
+    pow5config: Pow5Config<F, WIDTH, RATE>,
+}
+
+pub(super) struct PoseidonSynthesisOutput<F: ZktSimField> {
+    pub(super) message: AssignedCell<F, F>,
+    pub(super) output: AssignedCell<F, F>,
+}
+
+pub(super) struct PoseidonCommitOutput<F: ZktSimField> {
+    pub(super) message: Vec<AssignedCell<F, F>>,
+    pub(super) output: AssignedCell<F, F>,
+}
+
+/// Rows of a permutation region: the loaded state, then one row per full round and per
+/// pair of partial rounds. The rounds are the same over every field.
+fn permutation_rows() -> usize {
+    let full_rounds = <Poseidon128 as Spec<Fr, WIDTH, RATE>>::full_rounds();
+    let partial_rounds = <Poseidon128 as Spec<Fr, WIDTH, RATE>>::partial_rounds();
+    1 + full_rounds + partial_rounds / 2
+}
+
+/// Rows of a sponge absorbing `num_absorbs` padded blocks of RATE words, including the
+/// constant cells of its initial state.
+fn sponge_rows(num_absorbs: usize) -> usize {
+    // The initial state is loaded from WIDTH constants, then each block is added in three
+    // rows and permuted
+    1 + WIDTH + num_absorbs * (3 + permutation_rows())
+}
+
+/// Rows taken by [`PoseidonConfig::synthesize`] in the Poseidon columns.
+pub(super) fn hash_rows() -> usize {
+    1 + sponge_rows(L.div_ceil(RATE))
+}
+
+/// Rows taken by [`PoseidonConfig::commit`] of `num_words` words in the Poseidon
+/// columns.
+pub(super) fn commit_rows(num_words: usize) -> usize {
+    // The variable-length padding appends a one and pads to a multiple of RATE
+    num_words.div_ceil(WIDTH) + sponge_rows((num_words + 1).div_ceil(RATE))
+}
+
+impl<F: ZktSimField> PoseidonConfig<F> {
+    pub(super) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let state = (0..WIDTH).map(|_| meta.advice_column()).collect::<Vec<_>>();
+        let partial_sbox = meta.advice_column();
+
+        let rc_a = (0..WIDTH).map(|_| meta.fixed_column()).collect::<Vec<_>>();
+        let rc_b = (0..WIDTH).map(|_| meta.fixed_column()).collect::<Vec<_>>();
+
+        meta.enable_constant(rc_b[0]);
+
+        let pow5config = Pow5Chip::configure::<Poseidon128>(
+            meta,
+            state.try_into().unwrap(),
+            partial_sbox,
+            rc_a.try_into().unwrap(),
+            rc_b.try_into().unwrap(),
+        );
+
+        Self { pow5config }
+    }
+
+    pub(super) fn synthesize(
+        &self,
+        mut layouter: impl Layouter<F>,
+        msg_val: F,
+    ) -> Result<PoseidonSynthesisOutput<F>, Error> {
+        let chip = Pow5Chip::construct(self.pow5config.clone());
+
+        let mut msg_arr = [F::ZERO; L];
+        msg_arr[0] = msg_val;
+
+        let message: [AssignedCell<F, F>; L] = layouter.assign_region(
+            || "load message",
+            |mut region| {
+                let message_word = |i: usize| {
+                    region.assign_advice(
+                        || format!("load message_{}", i),
+                        self.pow5config.state[i],
+                        0,
+                        || Value::known(msg_arr[i]),
+                    )
+                };
+
+                let message: Result<Vec<_>, Error> = (0..L).map(message_word).collect();
+                Ok(message?.try_into().unwrap())
+            },
+        )?;
+
+        let hasher = Hash::<_, _, Poseidon128, ConstantLength<L>, WIDTH, RATE>::init(
+            chip,
+            layouter.namespace(|| "poseidon init"),
+        )?;
+
+        let output = hasher.hash(layouter.namespace(|| "do poseidon hash"), message.clone())?;
+
+        Ok(PoseidonSynthesisOutput {
+            message: message[0].clone(),
+            output,
+        })
+    }
+
+    /// Hashes an arbitrary number of field elements with the variable-length domain.
+    pub(super) fn commit(
+        &self,
+        mut layouter: impl Layouter<F>,
+        msg_vals: &[F],
+    ) -> Result<PoseidonCommitOutput<F>, Error> {
+        let chip = Pow5Chip::construct(self.pow5config.clone());
+
+        let message: Vec<AssignedCell<F, F>> = layouter.assign_region(
+            || "load commitment message",
+            |mut region| {
+                msg_vals
+                    .iter()
+                    .enumerate()
+                    .map(|(i, msg_val)| {
+                        region.assign_advice(
+                            || format!("load message_{}", i),
+                            self.pow5config.state[i % WIDTH],
+                            i / WIDTH,
+                            || Value::known(*msg_val),
+                        )
+                    })
+                    .collect()
+            },
+        )?;
+
+        let hasher = Hash::<_, _, Poseidon128, VariableLength, WIDTH, RATE>::init(
+            chip,
+            layouter.namespace(|| "poseidon init"),
+        )?;
+
+        let output = hasher.hash(
+            layouter.namespace(|| "do poseidon commitment"),
+            message.clone(),
+        )?;
+
+        Ok(PoseidonCommitOutput { message, output })
+    }
+}
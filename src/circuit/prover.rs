@@ -0,0 +1,1576 @@
+//! Proving and verifying zktsim proofs, with KZG commitments over BN256 or IPA commitments
+//! over the Pasta curves.
+//!
+//! The keys only depend on the [`CircuitShape`], not on the netlist or the wire values, so
+//! a verifier holding the verifying key and the public inputs can check a proof made on
+//! another machine.
+
+use std::{
+    fmt,
+    io::{Error as IoError, ErrorKind, Read, Write},
+};
+
+use ff::{FromUniformBytes, WithSmallOrderMulGroup};
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    plonk::{self, create_proof, keygen_pk, keygen_vk, verify_proof, Circuit},
+    poly::{
+        commitment::{CommitmentScheme, Params as _, ParamsProver, Prover, Verifier},
+        ipa::{
+            commitment::{IPACommitmentScheme, ParamsIPA},
+            multiopen::{ProverIPA, VerifierIPA},
+            strategy::SingleStrategy as IpaSingleStrategy,
+        },
+        kzg::{
+            commitment::{KZGCommitmentScheme, ParamsKZG},
+            multiopen::{ProverGWC, ProverSHPLONK, VerifierGWC, VerifierSHPLONK},
+            strategy::SingleStrategy,
+        },
+        VerificationStrategy,
+    },
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, Keccak256Read, Keccak256Write,
+        TranscriptReadBuffer, TranscriptWriterBuffer,
+    },
+    SerdeFormat,
+};
+use halo2curves::{
+    bn256::{Bn256, Fr, G1Affine},
+    pasta::{EqAffine, Fp},
+};
+use rand_core::OsRng;
+
+use crate::boolean_circuit::{BooleanCircuit, BooleanCircuitInstance, Partition};
+
+use super::field::ZktSimField;
+use super::mimc7_cbc_cipher::Mimc7CbcCipherConfig;
+use super::native::ROWS_PER_BLOCK;
+use super::netlist_binding::{NetlistBindingConfig, PoseidonSpongeConfig};
+use super::serialization::{
+    read_bytes, read_file, read_header, read_modulus, read_options, read_shape, write_bytes,
+    write_file, write_header, write_modulus, write_options, write_shape, Kind,
+};
+use super::{
+    BatchPublicInputs, CircuitSize, IoVisibility, NetlistBinding, NetlistBindingMode,
+    NetlistCommitment, PublicInputs, ZktSimCircuit,
+};
+
+pub use super::srs::{params_from_ptau_file, params_from_srs_file};
+pub use super::transcript::{PoseidonRead, PoseidonWrite};
+
+/// KZG parameters over BN256, which need a trusted setup.
+pub type Params = ParamsKZG<Bn256>;
+
+/// IPA parameters over the Pasta curves, which need no trusted setup, at the cost of
+/// larger proofs and a verifier linear in the circuit size.
+pub type IpaParams = ParamsIPA<EqAffine>;
+
+/// Encoding of the curve points of stored parameters and keys, checked to be on the curve
+/// when read.
+const SERDE_FORMAT: SerdeFormat = SerdeFormat::RawBytes;
+
+/// Multi-opening argument of KZG proofs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MultiOpen {
+    /// Batching of the PLONK paper, by Gabizon, Williamson and Ciobotaru: one opening
+    /// proof per set of rotations, and the cheapest prover
+    #[default]
+    Gwc,
+    /// Batching of Boneh, Drake, Fisch and Gabizon: two opening proofs whatever the
+    /// rotations, at the cost of a slower prover
+    Shplonk,
+}
+
+/// Hash function the prover derives its challenges with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TranscriptHash {
+    /// Fastest natively
+    #[default]
+    Blake2b,
+    /// Cheapest to recompute on the EVM, as required by Solidity verifiers
+    Keccak256,
+    /// Cheapest to recompute in a circuit, as required to verify the proof recursively;
+    /// see [`PoseidonWrite`]
+    Poseidon,
+}
+
+/// Choices of the proving system which a proof must be verified with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProofOptions {
+    /// Only used by KZG; IPA has a single multi-opening argument
+    pub multiopen: MultiOpen,
+    pub transcript: TranscriptHash,
+}
+
+/// Parameters of a polynomial commitment scheme, which select the proving backend: KZG
+/// over BN256 with [`Params`], or IPA over the Pasta curves with [`IpaParams`].
+pub trait Backend: Clone + fmt::Debug {
+    /// Field the circuit is built over
+    type Field: ZktSimField;
+    type Curve: CurveAffine<ScalarExt = Self::Field>;
+
+    /// Generates parameters for circuits of `2^k` rows. For KZG, see the caveats of
+    /// [`setup`].
+    fn generate(k: u32) -> Self;
+
+    fn params_k(&self) -> u32;
+
+    fn write_params(&self, writer: &mut impl Write) -> Result<(), IoError>;
+
+    fn read_params(reader: &mut impl Read) -> Result<Self, IoError>;
+
+    fn keygen<C: Circuit<Self::Field>>(
+        &self,
+        circuit: &C,
+    ) -> Result<plonk::ProvingKey<Self::Curve>, plonk::Error>;
+
+    fn write_pk(
+        pk: &plonk::ProvingKey<Self::Curve>,
+        writer: &mut impl Write,
+    ) -> Result<(), IoError>;
+
+    fn read_pk<C: Circuit<Self::Field>>(
+        reader: &mut impl Read,
+    ) -> Result<plonk::ProvingKey<Self::Curve>, IoError>;
+
+    fn write_vk(
+        vk: &plonk::VerifyingKey<Self::Curve>,
+        writer: &mut impl Write,
+    ) -> Result<(), IoError>;
+
+    fn read_vk<C: Circuit<Self::Field>>(
+        reader: &mut impl Read,
+    ) -> Result<plonk::VerifyingKey<Self::Curve>, IoError>;
+
+    fn create_proof<C: Circuit<Self::Field>>(
+        &self,
+        pk: &plonk::ProvingKey<Self::Curve>,
+        circuit: C,
+        instance: &[&[Self::Field]],
+        options: &ProofOptions,
+    ) -> Result<Vec<u8>, plonk::Error>;
+
+    fn verify_proof(
+        &self,
+        vk: &plonk::VerifyingKey<Self::Curve>,
+        instance: &[&[Self::Field]],
+        proof: &[u8],
+        options: &ProofOptions,
+    ) -> Result<(), plonk::Error>;
+}
+
+/// Creates a proof with the multi-opening prover `P` and the given transcript hash.
+fn create_proof_with<'params, Scheme, P, C>(
+    params: &'params Scheme::ParamsProver,
+    pk: &plonk::ProvingKey<Scheme::Curve>,
+    circuit: C,
+    instance: &[&[Scheme::Scalar]],
+    transcript: TranscriptHash,
+) -> Result<Vec<u8>, plonk::Error>
+where
+    Scheme: CommitmentScheme,
+    Scheme::Scalar: ZktSimField + WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+    P: Prover<'params, Scheme>,
+    C: Circuit<Scheme::Scalar>,
+{
+    let circuits = [circuit];
+    let instances = [instance];
+
+    match transcript {
+        TranscriptHash::Blake2b => {
+            let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+            create_proof::<Scheme, P, _, _, _, _>(
+                params,
+                pk,
+                &circuits,
+                &instances,
+                OsRng,
+                &mut transcript,
+            )?;
+            Ok(transcript.finalize())
+        }
+        TranscriptHash::Keccak256 => {
+            let mut transcript = Keccak256Write::<_, _, Challenge255<_>>::init(vec![]);
+            create_proof::<Scheme, P, _, _, _, _>(
+                params,
+                pk,
+                &circuits,
+                &instances,
+                OsRng,
+                &mut transcript,
+            )?;
+            Ok(transcript.finalize())
+        }
+        TranscriptHash::Poseidon => {
+            let mut transcript = PoseidonWrite::<_, _>::init(vec![]);
+            create_proof::<Scheme, P, _, _, _, _>(
+                params,
+                pk,
+                &circuits,
+                &instances,
+                OsRng,
+                &mut transcript,
+            )?;
+            Ok(transcript.finalize())
+        }
+    }
+}
+
+/// Verifies a proof with the multi-opening verifier `V` and the given transcript hash.
+fn verify_proof_with<'params, Scheme, V, S>(
+    params: &'params Scheme::ParamsVerifier,
+    vk: &plonk::VerifyingKey<Scheme::Curve>,
+    instance: &[&[Scheme::Scalar]],
+    proof: &[u8],
+    transcript: TranscriptHash,
+) -> Result<(), plonk::Error>
+where
+    Scheme: CommitmentScheme,
+    Scheme::Scalar: ZktSimField + WithSmallOrderMulGroup<3> + FromUniformBytes<64>,
+    V: Verifier<'params, Scheme>,
+    S: VerificationStrategy<'params, Scheme, V, Output = ()>,
+{
+    let strategy = S::new(params);
+    let instances = [instance];
+
+    match transcript {
+        TranscriptHash::Blake2b => {
+            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+            verify_proof::<Scheme, V, _, _, S>(params, vk, strategy, &instances, &mut transcript)
+        }
+        TranscriptHash::Keccak256 => {
+            let mut transcript = Keccak256Read::<_, _, Challenge255<_>>::init(proof);
+            verify_proof::<Scheme, V, _, _, S>(params, vk, strategy, &instances, &mut transcript)
+        }
+        TranscriptHash::Poseidon => {
+            let mut transcript = PoseidonRead::<_, _>::init(proof);
+            verify_proof::<Scheme, V, _, _, S>(params, vk, strategy, &instances, &mut transcript)
+        }
+    }
+}
+
+impl Backend for Params {
+    type Field = Fr;
+    type Curve = G1Affine;
+
+    fn generate(k: u32) -> Self {
+        ParamsKZG::setup(k, OsRng)
+    }
+
+    fn params_k(&self) -> u32 {
+        self.k()
+    }
+
+    fn write_params(&self, writer: &mut impl Write) -> Result<(), IoError> {
+        self.write_custom(writer, SERDE_FORMAT)
+    }
+
+    fn read_params(reader: &mut impl Read) -> Result<Self, IoError> {
+        Self::read_custom(reader, SERDE_FORMAT)
+    }
+
+    fn keygen<C: Circuit<Fr>>(
+        &self,
+        circuit: &C,
+    ) -> Result<plonk::ProvingKey<G1Affine>, plonk::Error> {
+        let vk = keygen_vk(self, circuit)?;
+        keygen_pk(self, vk, circuit)
+    }
+
+    fn write_pk(pk: &plonk::ProvingKey<G1Affine>, writer: &mut impl Write) -> Result<(), IoError> {
+        pk.write(writer, SERDE_FORMAT)
+    }
+
+    fn read_pk<C: Circuit<Fr>>(
+        reader: &mut impl Read,
+    ) -> Result<plonk::ProvingKey<G1Affine>, IoError> {
+        plonk::ProvingKey::read::<_, C>(reader, SERDE_FORMAT)
+    }
+
+    fn write_vk(
+        vk: &plonk::VerifyingKey<G1Affine>,
+        writer: &mut impl Write,
+    ) -> Result<(), IoError> {
+        vk.write(writer, SERDE_FORMAT)
+    }
+
+    fn read_vk<C: Circuit<Fr>>(
+        reader: &mut impl Read,
+    ) -> Result<plonk::VerifyingKey<G1Affine>, IoError> {
+        plonk::VerifyingKey::read::<_, C>(reader, SERDE_FORMAT)
+    }
+
+    fn create_proof<C: Circuit<Fr>>(
+        &self,
+        pk: &plonk::ProvingKey<G1Affine>,
+        circuit: C,
+        instance: &[&[Fr]],
+        options: &ProofOptions,
+    ) -> Result<Vec<u8>, plonk::Error> {
+        match options.multiopen {
+            MultiOpen::Gwc => create_proof_with::<
+                KZGCommitmentScheme<Bn256>,
+                ProverGWC<'_, Bn256>,
+                _,
+            >(self, pk, circuit, instance, options.transcript),
+            MultiOpen::Shplonk => create_proof_with::<
+                KZGCommitmentScheme<Bn256>,
+                ProverSHPLONK<'_, Bn256>,
+                _,
+            >(self, pk, circuit, instance, options.transcript),
+        }
+    }
+
+    fn verify_proof(
+        &self,
+        vk: &plonk::VerifyingKey<G1Affine>,
+        instance: &[&[Fr]],
+        proof: &[u8],
+        options: &ProofOptions,
+    ) -> Result<(), plonk::Error> {
+        match options.multiopen {
+            MultiOpen::Gwc => verify_proof_with::<
+                KZGCommitmentScheme<Bn256>,
+                VerifierGWC<'_, Bn256>,
+                SingleStrategy<'_, Bn256>,
+            >(self, vk, instance, proof, options.transcript),
+            MultiOpen::Shplonk => verify_proof_with::<
+                KZGCommitmentScheme<Bn256>,
+                VerifierSHPLONK<'_, Bn256>,
+                SingleStrategy<'_, Bn256>,
+            >(self, vk, instance, proof, options.transcript),
+        }
+    }
+}
+
+/// halo2 only serializes keys over curves with a raw point encoding, which the Pasta
+/// curves lack. IPA keys need no trusted setup, so they are regenerated instead.
+fn ipa_keys_unsupported() -> IoError {
+    IoError::new(
+        ErrorKind::Unsupported,
+        "keys over the Pasta curves cannot be stored, regenerate them with keygen",
+    )
+}
+
+impl Backend for IpaParams {
+    type Field = Fp;
+    type Curve = EqAffine;
+
+    fn generate(k: u32) -> Self {
+        ParamsIPA::new(k)
+    }
+
+    fn params_k(&self) -> u32 {
+        self.k()
+    }
+
+    fn write_params(&self, writer: &mut impl Write) -> Result<(), IoError> {
+        self.write(writer)
+    }
+
+    fn read_params(reader: &mut impl Read) -> Result<Self, IoError> {
+        Self::read(reader)
+    }
+
+    fn keygen<C: Circuit<Fp>>(
+        &self,
+        circuit: &C,
+    ) -> Result<plonk::ProvingKey<EqAffine>, plonk::Error> {
+        let vk = keygen_vk(self, circuit)?;
+        keygen_pk(self, vk, circuit)
+    }
+
+    fn write_pk(_: &plonk::ProvingKey<EqAffine>, _: &mut impl Write) -> Result<(), IoError> {
+        Err(ipa_keys_unsupported())
+    }
+
+    fn read_pk<C: Circuit<Fp>>(_: &mut impl Read) -> Result<plonk::ProvingKey<EqAffine>, IoError> {
+        Err(ipa_keys_unsupported())
+    }
+
+    fn write_vk(_: &plonk::VerifyingKey<EqAffine>, _: &mut impl Write) -> Result<(), IoError> {
+        Err(ipa_keys_unsupported())
+    }
+
+    fn read_vk<C: Circuit<Fp>>(
+        _: &mut impl Read,
+    ) -> Result<plonk::VerifyingKey<EqAffine>, IoError> {
+        Err(ipa_keys_unsupported())
+    }
+
+    fn create_proof<C: Circuit<Fp>>(
+        &self,
+        pk: &plonk::ProvingKey<EqAffine>,
+        circuit: C,
+        instance: &[&[Fp]],
+        options: &ProofOptions,
+    ) -> Result<Vec<u8>, plonk::Error> {
+        create_proof_with::<IPACommitmentScheme<EqAffine>, ProverIPA<'_, EqAffine>, _>(
+            self,
+            pk,
+            circuit,
+            instance,
+            options.transcript,
+        )
+    }
+
+    fn verify_proof(
+        &self,
+        vk: &plonk::VerifyingKey<EqAffine>,
+        instance: &[&[Fp]],
+        proof: &[u8],
+        options: &ProofOptions,
+    ) -> Result<(), plonk::Error> {
+        verify_proof_with::<
+            IPACommitmentScheme<EqAffine>,
+            VerifierIPA<'_, EqAffine>,
+            IpaSingleStrategy<'_, EqAffine>,
+        >(self, vk, instance, proof, options.transcript)
+    }
+}
+
+pub fn write_params<P: Backend>(params: &P, writer: &mut impl Write) -> Result<(), IoError> {
+    write_header(writer, Kind::Params)?;
+    write_modulus::<P::Field>(writer)?;
+    params.write_params(writer)
+}
+
+pub fn read_params<P: Backend>(reader: &mut impl Read) -> Result<P, IoError> {
+    read_header(reader, Kind::Params)?;
+    read_modulus::<P::Field>(reader)?;
+    P::read_params(reader)
+}
+
+pub fn params_from_file<P: Backend>(file_name: &str) -> Result<P, IoError> {
+    read_file(file_name, |reader| read_params(reader))
+}
+
+pub fn write_params_to_file<P: Backend>(params: &P, file_name: &str) -> Result<(), IoError> {
+    write_file(file_name, |writer| write_params(params, writer))
+}
+
+/// Everything the proving and verifying keys depend on, besides the parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitShape {
+    pub size: CircuitSize,
+    pub binding: NetlistBindingMode,
+    /// Committed input wires and committed output wires, in port order
+    pub committed_wires: [Vec<u64>; 2],
+}
+
+impl CircuitShape {
+    /// Fails if `visibility` has an entry naming no port of `ckt`.
+    pub fn new<F: ZktSimField>(
+        ckt: &BooleanCircuit,
+        visibility: &IoVisibility<F>,
+        binding: &NetlistBinding<F>,
+        size: CircuitSize,
+    ) -> Result<Self, IoError> {
+        visibility.check(ckt)?;
+        Ok(Self::of(ckt, visibility, binding, size))
+    }
+
+    fn of<F: ZktSimField>(
+        ckt: &BooleanCircuit,
+        visibility: &IoVisibility<F>,
+        binding: &NetlistBinding<F>,
+        size: CircuitSize,
+    ) -> Self {
+        Self {
+            size,
+            binding: binding.mode(),
+            committed_wires: visibility.committed_wires(ckt),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ProvingKey<P: Backend = Params> {
+    shape: CircuitShape,
+    pk: plonk::ProvingKey<P::Curve>,
+}
+
+impl<P: Backend> ProvingKey<P> {
+    pub fn shape(&self) -> &CircuitShape {
+        &self.shape
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey<P> {
+        VerifyingKey {
+            shape: self.shape.clone(),
+            vk: self.pk.get_vk().clone(),
+        }
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), IoError> {
+        write_header(writer, Kind::ProvingKey)?;
+        write_modulus::<P::Field>(writer)?;
+        write_shape(writer, &self.shape)?;
+        P::write_pk(&self.pk, writer)
+    }
+
+    /// Reads a proving key, failing unless it was generated for the given shape.
+    pub fn read(reader: &mut impl Read, shape: &CircuitShape) -> Result<Self, IoError> {
+        read_header(reader, Kind::ProvingKey)?;
+        read_modulus::<P::Field>(reader)?;
+        check_stored_shape(&read_shape(reader)?, shape)?;
+
+        let pk = match shape.binding {
+            NetlistBindingMode::Mimc7Cbc => {
+                P::read_pk::<ZktSimCircuit<P::Field, Mimc7CbcCipherConfig<P::Field>>>(reader)?
+            }
+            NetlistBindingMode::PoseidonSponge => {
+                P::read_pk::<ZktSimCircuit<P::Field, PoseidonSpongeConfig>>(reader)?
+            }
+        };
+        check_domain(pk.get_vk(), shape)?;
+
+        Ok(Self {
+            shape: shape.clone(),
+            pk,
+        })
+    }
+
+    pub fn from_file(file_name: &str, shape: &CircuitShape) -> Result<Self, IoError> {
+        read_file(file_name, |reader| Self::read(reader, shape))
+    }
+
+    pub fn write_to_file(&self, file_name: &str) -> Result<(), IoError> {
+        write_file(file_name, |writer| self.write(writer))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct VerifyingKey<P: Backend = Params> {
+    shape: CircuitShape,
+    vk: plonk::VerifyingKey<P::Curve>,
+}
+
+impl<P: Backend> VerifyingKey<P> {
+    pub fn shape(&self) -> &CircuitShape {
+        &self.shape
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), IoError> {
+        write_header(writer, Kind::VerifyingKey)?;
+        write_modulus::<P::Field>(writer)?;
+        write_shape(writer, &self.shape)?;
+        P::write_vk(&self.vk, writer)
+    }
+
+    /// Reads a verifying key, failing unless it was generated for the given shape.
+    pub fn read(reader: &mut impl Read, shape: &CircuitShape) -> Result<Self, IoError> {
+        read_header(reader, Kind::VerifyingKey)?;
+        read_modulus::<P::Field>(reader)?;
+        check_stored_shape(&read_shape(reader)?, shape)?;
+
+        let vk = match shape.binding {
+            NetlistBindingMode::Mimc7Cbc => {
+                P::read_vk::<ZktSimCircuit<P::Field, Mimc7CbcCipherConfig<P::Field>>>(reader)?
+            }
+            NetlistBindingMode::PoseidonSponge => {
+                P::read_vk::<ZktSimCircuit<P::Field, PoseidonSpongeConfig>>(reader)?
+            }
+        };
+        check_domain(&vk, shape)?;
+
+        Ok(Self {
+            shape: shape.clone(),
+            vk,
+        })
+    }
+
+    pub fn from_file(file_name: &str, shape: &CircuitShape) -> Result<Self, IoError> {
+        read_file(file_name, |reader| Self::read(reader, shape))
+    }
+
+    pub fn write_to_file(&self, file_name: &str) -> Result<(), IoError> {
+        write_file(file_name, |writer| self.write(writer))
+    }
+}
+
+fn check_stored_shape(stored: &CircuitShape, shape: &CircuitShape) -> Result<(), IoError> {
+    if stored != shape {
+        return Err(IoError::new(
+            ErrorKind::InvalidData,
+            format!("key was generated for {:?}, expected {:?}", stored, shape),
+        ));
+    }
+    Ok(())
+}
+
+fn check_domain<C: CurveAffine>(
+    vk: &plonk::VerifyingKey<C>,
+    shape: &CircuitShape,
+) -> Result<(), IoError> {
+    let k = vk.get_domain().k();
+    if k != shape.size.k {
+        return Err(IoError::new(
+            ErrorKind::InvalidData,
+            format!("key is for k = {}, expected k = {}", k, shape.size.k),
+        ));
+    }
+    Ok(())
+}
+
+/// Proof transcript, to be checked against the public inputs of the proof.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+    pub bytes: Vec<u8>,
+    /// Options the proof was made with, and must be verified with
+    pub options: ProofOptions,
+}
+
+impl Proof {
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), IoError> {
+        write_header(writer, Kind::Proof)?;
+        write_options(writer, &self.options)?;
+        write_bytes(writer, &self.bytes)
+    }
+
+    pub fn read(reader: &mut impl Read) -> Result<Self, IoError> {
+        read_header(reader, Kind::Proof)?;
+        Ok(Self {
+            options: read_options(reader)?,
+            bytes: read_bytes(reader)?,
+        })
+    }
+
+    pub fn from_file(file_name: &str) -> Result<Self, IoError> {
+        read_file(file_name, |reader| Self::read(reader))
+    }
+
+    pub fn write_to_file(&self, file_name: &str) -> Result<(), IoError> {
+        write_file(file_name, |writer| self.write(writer))
+    }
+}
+
+#[derive(Debug)]
+pub enum VerifyError {
+    /// The public inputs do not fit the shape of the verifying key
+    PublicInputs(String),
+    /// The proof was made with other options than those it is verified with
+    Options {
+        proof: ProofOptions,
+        expected: ProofOptions,
+    },
+    /// The proof does not verify against the public inputs
+    InvalidProof(plonk::Error),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PublicInputs(reason) => write!(f, "invalid public inputs: {}", reason),
+            Self::Options { proof, expected } => write!(
+                f,
+                "proof was made with {:?}, expected {:?}",
+                proof, expected
+            ),
+            Self::InvalidProof(err) => write!(f, "invalid proof: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Error of key generation or proving.
+#[derive(Debug)]
+pub enum ProveError {
+    /// The inputs, the visibility or the parameters do not fit the circuit or the key
+    Io(IoError),
+    /// The proving system failed, e.g. because the witness does not satisfy the circuit
+    Plonk(plonk::Error),
+}
+
+impl fmt::Display for ProveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{}", err),
+            Self::Plonk(err) => write!(f, "proving system error: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for ProveError {}
+
+impl From<IoError> for ProveError {
+    fn from(err: IoError) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Generates KZG parameters for circuits of `2^k` rows from fresh randomness.
+///
+/// Whoever runs the setup can forge proofs, so these parameters are only fit for testing
+/// or for a prover verifying its own proofs; see [`params_from_ptau_file`] and
+/// [`params_from_srs_file`] for the parameters of public ceremonies, or [`IpaParams`] for
+/// a backend without trusted setup.
+pub fn setup(k: u32) -> Params {
+    Params::generate(k)
+}
+
+/// Generates the keys of circuits of the given shape.
+pub fn keygen<P: Backend>(params: &P, shape: &CircuitShape) -> Result<ProvingKey<P>, ProveError> {
+    if params.params_k() != shape.size.k {
+        return Err(IoError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "parameters are for k = {}, the circuit needs k = {}",
+                params.params_k(),
+                shape.size.k
+            ),
+        )
+        .into());
+    }
+
+    match shape.binding {
+        NetlistBindingMode::Mimc7Cbc => {
+            keygen_with::<P, Mimc7CbcCipherConfig<P::Field>>(params, shape)
+        }
+        NetlistBindingMode::PoseidonSponge => keygen_with::<P, PoseidonSpongeConfig>(params, shape),
+    }
+}
+
+fn keygen_with<P: Backend, B: NetlistBindingConfig<P::Field>>(
+    params: &P,
+    shape: &CircuitShape,
+) -> Result<ProvingKey<P>, ProveError> {
+    let circuit =
+        ZktSimCircuit::<P::Field, B>::with_shape(shape.size, shape.committed_wires.clone());
+
+    let pk = params.keygen(&circuit).map_err(ProveError::Plonk)?;
+
+    Ok(ProvingKey {
+        shape: shape.clone(),
+        pk,
+    })
+}
+
+/// Proves that the circuit instance satisfies its netlist with the default
+/// [`ProofOptions`], returning the proof with its public inputs.
+///
+/// Fails if `visibility` names a port the circuit does not have, or if the circuit does
+/// not have the shape of the proving key.
+pub fn prove<P: Backend>(
+    params: &P,
+    pk: &ProvingKey<P>,
+    inst: BooleanCircuitInstance,
+    visibility: &IoVisibility<P::Field>,
+    binding: &NetlistBinding<P::Field>,
+) -> Result<(Proof, PublicInputs<P::Field>), ProveError> {
+    prove_with_options(
+        params,
+        pk,
+        inst,
+        visibility,
+        binding,
+        &ProofOptions::default(),
+    )
+}
+
+/// Same as [`prove`], with the given multi-opening argument and transcript hash.
+pub fn prove_with_options<P: Backend>(
+    params: &P,
+    pk: &ProvingKey<P>,
+    inst: BooleanCircuitInstance,
+    visibility: &IoVisibility<P::Field>,
+    binding: &NetlistBinding<P::Field>,
+    options: &ProofOptions,
+) -> Result<(Proof, PublicInputs<P::Field>), ProveError> {
+    visibility.check(&inst.ckt)?;
+    prove_instance(params, pk, inst, visibility, binding, options)
+}
+
+/// Proves `inst` without checking that `visibility` only names its ports, as the parts of
+/// a partition each have a subset of the primary ports.
+fn prove_instance<P: Backend>(
+    params: &P,
+    pk: &ProvingKey<P>,
+    inst: BooleanCircuitInstance,
+    visibility: &IoVisibility<P::Field>,
+    binding: &NetlistBinding<P::Field>,
+    options: &ProofOptions,
+) -> Result<(Proof, PublicInputs<P::Field>), ProveError> {
+    let size = pk.shape.size;
+    let shape = CircuitShape::of(&inst.ckt, visibility, binding, size);
+    if shape != pk.shape || !size.fits(&inst.ckt, visibility, binding) {
+        return Err(IoError::new(
+            ErrorKind::InvalidInput,
+            "circuit does not have the shape of the proving key",
+        )
+        .into());
+    }
+
+    let netlist_commitment = NetlistCommitment::compute(&inst.ckt, binding, size.num_gate_rows);
+    let public_inputs = PublicInputs::new(&inst, visibility, netlist_commitment);
+
+    let proof = match binding {
+        NetlistBinding::Mimc7Cbc(_) => {
+            let circuit = ZktSimCircuit::<P::Field, Mimc7CbcCipherConfig<P::Field>>::new(
+                size,
+                inst,
+                visibility,
+                binding.secret(),
+            );
+            prove_circuit(
+                params,
+                pk,
+                circuit,
+                public_inputs.instance_columns(),
+                options,
+            )
+        }
+        NetlistBinding::PoseidonSponge { .. } => {
+            let circuit = ZktSimCircuit::<P::Field, PoseidonSpongeConfig>::new(
+                size,
+                inst,
+                visibility,
+                binding.secret(),
+            );
+            prove_circuit(
+                params,
+                pk,
+                circuit,
+                public_inputs.instance_columns(),
+                options,
+            )
+        }
+    }?;
+
+    Ok((proof, public_inputs))
+}
+
+fn prove_circuit<P: Backend, B: NetlistBindingConfig<P::Field>>(
+    params: &P,
+    pk: &ProvingKey<P>,
+    circuit: ZktSimCircuit<P::Field, B>,
+    instance_columns: Vec<Vec<P::Field>>,
+    options: &ProofOptions,
+) -> Result<Proof, ProveError> {
+    let instance = instance_columns
+        .iter()
+        .map(|column| column.as_slice())
+        .collect::<Vec<_>>();
+
+    let proof = params
+        .create_proof(&pk.pk, circuit, &instance, options)
+        .map_err(ProveError::Plonk)?;
+
+    Ok(Proof {
+        bytes: proof,
+        options: *options,
+    })
+}
+
+/// Verifies a proof made with the default [`ProofOptions`] against its public inputs.
+pub fn verify<P: Backend>(
+    params: &P,
+    vk: &VerifyingKey<P>,
+    public_inputs: &PublicInputs<P::Field>,
+    proof: &Proof,
+) -> Result<(), VerifyError> {
+    verify_with_options(params, vk, public_inputs, proof, &ProofOptions::default())
+}
+
+/// Same as [`verify`], for a proof made with the given options.
+pub fn verify_with_options<P: Backend>(
+    params: &P,
+    vk: &VerifyingKey<P>,
+    public_inputs: &PublicInputs<P::Field>,
+    proof: &Proof,
+    options: &ProofOptions,
+) -> Result<(), VerifyError> {
+    check_shape(
+        &vk.shape,
+        public_inputs.num_gates,
+        &public_inputs.public_wires,
+        &public_inputs.netlist_commitment,
+    )
+    .map_err(VerifyError::PublicInputs)?;
+
+    verify_instance(params, vk, public_inputs.instance_columns(), proof, options)
+}
+
+/// Proves that the netlist evaluates to the computed outputs on each of the input
+/// vectors, in a single proof revealing all of them, with the default [`ProofOptions`].
+///
+/// Fails if the proving key is not for batches of all-public circuits of this shape, or
+/// holds fewer vectors than given.
+pub fn prove_batch<P: Backend>(
+    params: &P,
+    pk: &ProvingKey<P>,
+    ckt: BooleanCircuit,
+    inputs: &[Vec<bool>],
+    binding: &NetlistBinding<P::Field>,
+) -> Result<(Proof, BatchPublicInputs<P::Field>), ProveError> {
+    prove_batch_with_options(params, pk, ckt, inputs, binding, &ProofOptions::default())
+}
+
+/// Same as [`prove_batch`], with the given multi-opening argument and transcript hash.
+pub fn prove_batch_with_options<P: Backend>(
+    params: &P,
+    pk: &ProvingKey<P>,
+    ckt: BooleanCircuit,
+    inputs: &[Vec<bool>],
+    binding: &NetlistBinding<P::Field>,
+    options: &ProofOptions,
+) -> Result<(Proof, BatchPublicInputs<P::Field>), ProveError> {
+    let size = pk.shape.size;
+    let shape = CircuitShape::of(&ckt, &IoVisibility::all_public(), binding, size);
+    if shape != pk.shape || !size.fits_batch(&ckt, binding, inputs.len()) {
+        return Err(IoError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "batch of {} vectors does not have the shape of the proving key",
+                inputs.len()
+            ),
+        )
+        .into());
+    }
+    if let Some(vector) = inputs.iter().find(|v| v.len() != ckt.inputs.len()) {
+        return Err(IoError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "input vector has {} values, the circuit has {} inputs",
+                vector.len(),
+                ckt.inputs.len()
+            ),
+        )
+        .into());
+    }
+
+    let assignments = inputs.iter().map(|v| ckt.eval(v)).collect::<Vec<_>>();
+    let netlist_commitment = NetlistCommitment::compute(&ckt, binding, size.num_gate_rows);
+    let public_inputs = BatchPublicInputs::new(&ckt, &assignments, netlist_commitment);
+    let instance_columns = public_inputs.instance_columns(&size);
+
+    let proof = match binding {
+        NetlistBinding::Mimc7Cbc(_) => {
+            let circuit = ZktSimCircuit::<P::Field, Mimc7CbcCipherConfig<P::Field>>::new_batch(
+                size,
+                ckt,
+                assignments,
+                binding.secret(),
+            );
+            prove_circuit(params, pk, circuit, instance_columns, options)
+        }
+        NetlistBinding::PoseidonSponge { .. } => {
+            let circuit = ZktSimCircuit::<P::Field, PoseidonSpongeConfig>::new_batch(
+                size,
+                ckt,
+                assignments,
+                binding.secret(),
+            );
+            prove_circuit(params, pk, circuit, instance_columns, options)
+        }
+    }?;
+
+    Ok((proof, public_inputs))
+}
+
+/// Verifies a batch proof made with the default [`ProofOptions`] against its public
+/// inputs.
+pub fn verify_batch<P: Backend>(
+    params: &P,
+    vk: &VerifyingKey<P>,
+    public_inputs: &BatchPublicInputs<P::Field>,
+    proof: &Proof,
+) -> Result<(), VerifyError> {
+    verify_batch_with_options(params, vk, public_inputs, proof, &ProofOptions::default())
+}
+
+/// Same as [`verify_batch`], for a proof made with the given options.
+pub fn verify_batch_with_options<P: Backend>(
+    params: &P,
+    vk: &VerifyingKey<P>,
+    public_inputs: &BatchPublicInputs<P::Field>,
+    proof: &Proof,
+    options: &ProofOptions,
+) -> Result<(), VerifyError> {
+    let size = &vk.shape.size;
+    if !(1..=size.batch_size).contains(&public_inputs.vectors.len()) {
+        return Err(VerifyError::PublicInputs(format!(
+            "{} vectors do not fit in batches of {}",
+            public_inputs.vectors.len(),
+            size.batch_size
+        )));
+    }
+    for wires in public_inputs.vectors.iter() {
+        check_shape(
+            &vk.shape,
+            public_inputs.num_gates,
+            wires,
+            &public_inputs.netlist_commitment,
+        )
+        .map_err(VerifyError::PublicInputs)?;
+    }
+
+    verify_instance(
+        params,
+        vk,
+        public_inputs.instance_columns(size),
+        proof,
+        options,
+    )
+}
+
+/// Proves each part of a partitioned circuit on the primary inputs with the default
+/// [`ProofOptions`], committing the cut wires instead of revealing them.
+///
+/// The proving key is for the shape of the parts, see [`Partition::shape`]. Fails if a
+/// primary port is committed or missing, or if the parts do not have the shape of the
+/// proving key.
+pub fn prove_partitioned<P: Backend>(
+    params: &P,
+    pk: &ProvingKey<P>,
+    partition: &Partition,
+    inputs: &[bool],
+    visibility: &IoVisibility<P::Field>,
+    binding: &NetlistBinding<P::Field>,
+) -> Result<Vec<(Proof, PublicInputs<P::Field>)>, ProveError> {
+    prove_partitioned_with_options(
+        params,
+        pk,
+        partition,
+        inputs,
+        visibility,
+        binding,
+        &ProofOptions::default(),
+    )
+}
+
+/// Same as [`prove_partitioned`], with the given multi-opening argument and transcript hash.
+pub fn prove_partitioned_with_options<P: Backend>(
+    params: &P,
+    pk: &ProvingKey<P>,
+    partition: &Partition,
+    inputs: &[bool],
+    visibility: &IoVisibility<P::Field>,
+    binding: &NetlistBinding<P::Field>,
+    options: &ProofOptions,
+) -> Result<Vec<(Proof, PublicInputs<P::Field>)>, ProveError> {
+    let visibility = partition.visibility(visibility)?;
+
+    let num_inputs = partition.parts[0].inputs.len() - partition.cut_width;
+    if inputs.len() != num_inputs {
+        return Err(IoError::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} input values given, the circuit has {} inputs",
+                inputs.len(),
+                num_inputs
+            ),
+        )
+        .into());
+    }
+
+    partition
+        .eval(inputs)
+        .into_iter()
+        .map(|inst| prove_instance(params, pk, inst, &visibility, binding, options))
+        .collect()
+}
+
+/// Verifies the proofs of the parts of a partitioned circuit, in order, made with the
+/// default [`ProofOptions`].
+///
+/// Besides each proof, checks that there is one proof per expected netlist commitment,
+/// each committing to the netlist of its part, see [`Partition::netlist_commitments`];
+/// that the first part starts from the all-zero cut committed to by
+/// `first_cut_commitment`, see [`Partition::first_cut_commitment`]; and that every other
+/// part starts from the cut wires the previous part ended with, i.e. that their wire
+/// commitments match.
+pub fn verify_partitioned<P: Backend>(
+    params: &P,
+    vk: &VerifyingKey<P>,
+    proofs: &[(Proof, PublicInputs<P::Field>)],
+    netlist_commitments: &[NetlistCommitment<P::Field>],
+    first_cut_commitment: P::Field,
+) -> Result<(), VerifyError> {
+    verify_partitioned_with_options(
+        params,
+        vk,
+        proofs,
+        netlist_commitments,
+        first_cut_commitment,
+        &ProofOptions::default(),
+    )
+}
+
+/// Same as [`verify_partitioned`], for proofs made with the given options.
+pub fn verify_partitioned_with_options<P: Backend>(
+    params: &P,
+    vk: &VerifyingKey<P>,
+    proofs: &[(Proof, PublicInputs<P::Field>)],
+    netlist_commitments: &[NetlistCommitment<P::Field>],
+    first_cut_commitment: P::Field,
+    options: &ProofOptions,
+) -> Result<(), VerifyError> {
+    if proofs.is_empty() {
+        return Err(VerifyError::PublicInputs("no parts to verify".to_string()));
+    }
+    if proofs.len() != netlist_commitments.len() {
+        return Err(VerifyError::PublicInputs(format!(
+            "{} proofs given, the circuit has {} parts",
+            proofs.len(),
+            netlist_commitments.len()
+        )));
+    }
+
+    for (i, ((_, part), expected)) in proofs.iter().zip(netlist_commitments).enumerate() {
+        if part.netlist_commitment != *expected {
+            return Err(VerifyError::PublicInputs(format!(
+                "part {} does not commit to the expected netlist",
+                i
+            )));
+        }
+    }
+
+    if proofs[0].1.wire_commitments[0] != first_cut_commitment {
+        return Err(VerifyError::PublicInputs(
+            "part 0 does not start from the all-zero cut".to_string(),
+        ));
+    }
+    for (i, pair) in proofs.windows(2).enumerate() {
+        let (_, part) = &pair[0];
+        let (_, next_part) = &pair[1];
+        if part.wire_commitments[1] != next_part.wire_commitments[0] {
+            return Err(VerifyError::PublicInputs(format!(
+                "part {} does not start from the cut wires of part {}",
+                i + 1,
+                i
+            )));
+        }
+    }
+
+    for (proof, public_inputs) in proofs.iter() {
+        verify_with_options(params, vk, public_inputs, proof, options)?;
+    }
+    Ok(())
+}
+
+fn verify_instance<P: Backend>(
+    params: &P,
+    vk: &VerifyingKey<P>,
+    instance_columns: Vec<Vec<P::Field>>,
+    proof: &Proof,
+    options: &ProofOptions,
+) -> Result<(), VerifyError> {
+    check_options(proof, options)?;
+
+    let instance = instance_columns
+        .iter()
+        .map(|column| column.as_slice())
+        .collect::<Vec<_>>();
+
+    params
+        .verify_proof(&vk.vk, &instance, &proof.bytes, options)
+        .map_err(VerifyError::InvalidProof)
+}
+
+fn check_options(proof: &Proof, options: &ProofOptions) -> Result<(), VerifyError> {
+    if proof.options != *options {
+        return Err(VerifyError::Options {
+            proof: proof.options,
+            expected: *options,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that the public inputs of a vector can belong to a circuit of the given shape.
+fn check_shape<F: ZktSimField>(
+    shape: &CircuitShape,
+    num_gates: usize,
+    public_wires: &[(u64, bool)],
+    netlist_commitment: &NetlistCommitment<F>,
+) -> Result<(), String> {
+    let size = &shape.size;
+
+    if num_gates > size.num_gate_rows {
+        return Err(format!(
+            "{} gates exceed the {} gate rows",
+            num_gates, size.num_gate_rows
+        ));
+    }
+
+    if let Some(&(w, _)) = public_wires
+        .iter()
+        .find(|&&(w, _)| w as usize >= size.num_wire_rows)
+    {
+        return Err(format!(
+            "wire {} exceeds the {} wire rows",
+            w, size.num_wire_rows
+        ));
+    }
+
+    match (netlist_commitment, shape.binding) {
+        (NetlistCommitment::Mimc7Cbc { ciphertext, .. }, NetlistBindingMode::Mimc7Cbc) => {
+            let num_blocks = size.num_gate_rows / ROWS_PER_BLOCK;
+            if ciphertext.len() != num_blocks {
+                return Err(format!(
+                    "ciphertext has {} blocks instead of {}",
+                    ciphertext.len(),
+                    num_blocks
+                ));
+            }
+        }
+        (
+            NetlistCommitment::PoseidonSponge { num_gate_rows, .. },
+            NetlistBindingMode::PoseidonSponge,
+        ) => {
+            if *num_gate_rows != size.num_gate_rows {
+                return Err(format!(
+                    "digest is over {} gate rows instead of {}",
+                    num_gate_rows, size.num_gate_rows
+                ));
+            }
+        }
+        _ => return Err("netlist commitment does not match the binding mode".to_string()),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::EncryptionKey;
+
+    fn cla_adder() -> BooleanCircuitInstance {
+        let ckt = BooleanCircuit::from_netlist("examples/cla_adder_6b.zkt").unwrap();
+        let inputs = vec![true; ckt.inputs.len()];
+        BooleanCircuitInstance::from_ckt_and_inputs(ckt, &inputs)
+    }
+
+    #[test]
+    fn proofs_verify_against_their_public_inputs_only() {
+        let inst = cla_adder();
+        let visibility = IoVisibility::all_public();
+        let binding = NetlistBinding::Mimc7Cbc(EncryptionKey::random());
+
+        let size = CircuitSize::new(&inst.ckt, &visibility, &binding);
+        let params = setup(size.k);
+        let shape = CircuitShape::new(&inst.ckt, &visibility, &binding, size).unwrap();
+        let pk = keygen(&params, &shape).unwrap();
+        let vk = pk.verifying_key();
+
+        let (proof, public_inputs) = prove(&params, &pk, inst, &visibility, &binding).unwrap();
+        assert!(verify(&params, &vk, &public_inputs, &proof).is_ok());
+
+        let mut flipped = public_inputs.clone();
+        flipped.public_wires[0].1 ^= true;
+        assert!(matches!(
+            verify(&params, &vk, &flipped, &proof),
+            Err(VerifyError::InvalidProof(_))
+        ));
+
+        let mut too_many_gates = public_inputs;
+        too_many_gates.num_gates = size.num_gate_rows + 1;
+        assert!(matches!(
+            verify(&params, &vk, &too_many_gates, &proof),
+            Err(VerifyError::PublicInputs(_))
+        ));
+    }
+
+    #[test]
+    fn found_inputs_are_proven_privately() {
+        let ckt = cla_adder().ckt;
+        let target = BooleanCircuitInstance::from_ckt_and_inputs(
+            ckt.clone(),
+            &(0..ckt.inputs.len())
+                .map(|i| i % 3 == 0)
+                .collect::<Vec<_>>(),
+        );
+        let outputs = ckt
+            .outputs
+            .iter()
+            .map(|&w| target.assn.wires[w as usize])
+            .collect::<Vec<_>>();
+
+        let inst = BooleanCircuitInstance::from_ckt_and_outputs(ckt.clone(), &outputs)
+            .unwrap()
+            .unwrap();
+        let visibility = IoVisibility::private_inputs(&["a", "b", "c_in"]);
+        let binding = NetlistBinding::PoseidonSponge { salt: Fr::from(6) };
+
+        let size = CircuitSize::new(&inst.ckt, &visibility, &binding);
+        let params = setup(size.k);
+        let shape = CircuitShape::new(&inst.ckt, &visibility, &binding, size).unwrap();
+        let pk = keygen(&params, &shape).unwrap();
+        let (proof, public_inputs) = prove(&params, &pk, inst, &visibility, &binding).unwrap();
+        assert!(verify(&params, &pk.verifying_key(), &public_inputs, &proof).is_ok());
+
+        // Only the outputs are revealed
+        let expected = ckt.outputs.iter().copied().zip(outputs).collect::<Vec<_>>();
+        assert_eq!(public_inputs.public_wires, expected);
+        assert_eq!(public_inputs.wire_commitments, [Fr::zero(); 2]);
+    }
+
+    #[test]
+    fn stored_proofs_verify_with_stored_keys() {
+        let inst = cla_adder();
+        let visibility = IoVisibility::all_public();
+        let binding = NetlistBinding::PoseidonSponge { salt: Fr::from(5) };
+
+        let size = CircuitSize::new(&inst.ckt, &visibility, &binding);
+        let params = setup(size.k);
+        let shape = CircuitShape::new(&inst.ckt, &visibility, &binding, size).unwrap();
+        let pk = keygen(&params, &shape).unwrap();
+        let (proof, public_inputs) = prove(&params, &pk, inst, &visibility, &binding).unwrap();
+
+        let mut params_bytes = vec![];
+        write_params(&params, &mut params_bytes).unwrap();
+        let mut vk_bytes = vec![];
+        pk.verifying_key().write(&mut vk_bytes).unwrap();
+        let mut proof_bytes = vec![];
+        proof.write(&mut proof_bytes).unwrap();
+        let mut public_inputs_bytes = vec![];
+        public_inputs.write(&mut public_inputs_bytes).unwrap();
+
+        let params = read_params::<Params>(&mut &params_bytes[..]).unwrap();
+        let vk = VerifyingKey::<Params>::read(&mut &vk_bytes[..], &shape).unwrap();
+        let proof = Proof::read(&mut &proof_bytes[..]).unwrap();
+        let public_inputs = PublicInputs::<Fr>::read(&mut &public_inputs_bytes[..]).unwrap();
+        assert!(verify(&params, &vk, &public_inputs, &proof).is_ok());
+
+        let other_shape = CircuitShape {
+            binding: NetlistBindingMode::Mimc7Cbc,
+            ..shape.clone()
+        };
+        assert!(VerifyingKey::<Params>::read(&mut &vk_bytes[..], &other_shape).is_err());
+        assert!(Proof::read(&mut &vk_bytes[..]).is_err());
+        assert!(read_params::<IpaParams>(&mut &params_bytes[..]).is_err());
+    }
+
+    #[test]
+    fn proofs_verify_with_the_options_they_were_made_with() {
+        let inst = cla_adder();
+        let visibility = IoVisibility::all_public();
+        let binding = NetlistBinding::PoseidonSponge { salt: Fr::from(9) };
+
+        let size = CircuitSize::new(&inst.ckt, &visibility, &binding);
+        let params = setup(size.k);
+        let shape = CircuitShape::new(&inst.ckt, &visibility, &binding, size).unwrap();
+        let pk = keygen(&params, &shape).unwrap();
+        let vk = pk.verifying_key();
+
+        let all_options = [MultiOpen::Gwc, MultiOpen::Shplonk]
+            .into_iter()
+            .flat_map(|multiopen| {
+                [
+                    TranscriptHash::Blake2b,
+                    TranscriptHash::Keccak256,
+                    TranscriptHash::Poseidon,
+                ]
+                .map(|transcript| ProofOptions {
+                    multiopen,
+                    transcript,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for options in all_options.iter() {
+            let (proof, public_inputs) =
+                prove_with_options(&params, &pk, cla_adder(), &visibility, &binding, options)
+                    .unwrap();
+
+            let mut proof_bytes = vec![];
+            proof.write(&mut proof_bytes).unwrap();
+            assert_eq!(Proof::read(&mut &proof_bytes[..]).unwrap(), proof);
+
+            for other in all_options.iter() {
+                let verified = verify_with_options(&params, &vk, &public_inputs, &proof, other);
+                if options == other {
+                    assert!(verified.is_ok(), "{:?}", options);
+                } else {
+                    assert!(matches!(verified, Err(VerifyError::Options { .. })));
+                }
+
+                // Relabeling the options does not make the transcript verify
+                let relabeled = Proof {
+                    options: *other,
+                    ..proof.clone()
+                };
+                let verified =
+                    verify_with_options(&params, &vk, &public_inputs, &relabeled, other).is_ok();
+                assert_eq!(
+                    verified,
+                    options == other,
+                    "{:?} checked as {:?}",
+                    options,
+                    other
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn batch_proofs_verify_against_every_vector() {
+        let ckt = cla_adder().ckt;
+        let binding = NetlistBinding::Mimc7Cbc(EncryptionKey::random());
+        let inputs = (0..3u64)
+            .map(|v| {
+                (0..ckt.inputs.len())
+                    .map(|i| (v + i as u64) % 3 == 0)
+                    .collect()
+            })
+            .collect::<Vec<Vec<bool>>>();
+
+        let size = CircuitSize::new_batch(&ckt, &binding, 4);
+        let params = setup(size.k);
+        let shape =
+            CircuitShape::new(&ckt, &IoVisibility::<Fr>::all_public(), &binding, size).unwrap();
+        let pk = keygen(&params, &shape).unwrap();
+        let vk = pk.verifying_key();
+
+        let (proof, public_inputs) =
+            prove_batch(&params, &pk, ckt.clone(), &inputs, &binding).unwrap();
+        assert_eq!(public_inputs.vectors.len(), 3);
+        assert!(verify_batch(&params, &vk, &public_inputs, &proof).is_ok());
+
+        let mut flipped = public_inputs.clone();
+        flipped.vectors[2][0].1 ^= true;
+        assert!(matches!(
+            verify_batch(&params, &vk, &flipped, &proof),
+            Err(VerifyError::InvalidProof(_))
+        ));
+
+        let mut dropped = public_inputs;
+        dropped.vectors.pop();
+        assert!(verify_batch(&params, &vk, &dropped, &proof).is_err());
+
+        let too_many = vec![inputs[0].clone(); 5];
+        assert!(prove_batch(&params, &pk, ckt, &too_many, &binding).is_err());
+    }
+
+    #[test]
+    fn partitioned_proofs_link_their_cut_wires() {
+        let ckt = cla_adder().ckt;
+        let partition = ckt.partition(10).unwrap();
+        let visibility = IoVisibility {
+            commitment_salt: Fr::from(11),
+            ..Default::default()
+        };
+        let binding = NetlistBinding::PoseidonSponge { salt: Fr::from(4) };
+
+        let shape = partition.shape(binding.mode());
+        let params = setup(shape.size.k);
+        let pk = keygen(&params, &shape).unwrap();
+        let vk = pk.verifying_key();
+
+        let prove_inputs = |value: bool| {
+            let inputs = vec![value; ckt.inputs.len()];
+            prove_partitioned(&params, &pk, &partition, &inputs, &visibility, &binding).unwrap()
+        };
+        let proofs = prove_inputs(true);
+        assert_eq!(proofs.len(), partition.parts.len());
+        let misspelled = IoVisibility::private_inputs(&["sum", "c_in"]);
+        let inputs = vec![true; ckt.inputs.len()];
+        assert!(
+            prove_partitioned(&params, &pk, &partition, &inputs, &misspelled, &binding).is_err()
+        );
+        let netlist_commitments = partition.netlist_commitments(&binding);
+        let first_cut = partition.first_cut_commitment(visibility.commitment_salt);
+        let verify = |proofs: &[(Proof, PublicInputs)],
+                      netlist_commitments: &[NetlistCommitment]| {
+            verify_partitioned(&params, &vk, proofs, netlist_commitments, first_cut)
+        };
+        assert!(verify(&proofs, &netlist_commitments).is_ok());
+        for ((_, public_inputs), part) in proofs.iter().zip(partition.parts.iter()) {
+            assert!(public_inputs
+                .netlist_commitment
+                .is_commitment_to(part, &binding));
+        }
+
+        // A part proven on other inputs does not continue the evaluation
+        let mut mixed = proofs.clone();
+        mixed[1] = prove_inputs(false).swap_remove(1);
+        assert!(matches!(
+            verify(&mixed, &netlist_commitments),
+            Err(VerifyError::PublicInputs(_))
+        ));
+
+        let mut reordered = proofs.clone();
+        reordered.swap(0, 1);
+        assert!(matches!(
+            verify(&reordered, &netlist_commitments),
+            Err(VerifyError::PublicInputs(_))
+        ));
+
+        // Dropping the last parts leaves the evaluation unfinished
+        let prefix = &proofs[..proofs.len() - 1];
+        assert!(matches!(
+            verify(prefix, &netlist_commitments),
+            Err(VerifyError::PublicInputs(_))
+        ));
+
+        // Parts of another netlist are not bound, even with a consistent chain of cuts
+        let other = NetlistBinding::PoseidonSponge { salt: Fr::from(5) };
+        assert!(matches!(
+            verify(&proofs, &partition.netlist_commitments(&other)),
+            Err(VerifyError::PublicInputs(_))
+        ));
+
+        // The first part must start from the all-zero cut
+        assert!(matches!(
+            verify_partitioned(
+                &params,
+                &vk,
+                &proofs,
+                &netlist_commitments,
+                partition.first_cut_commitment(Fr::from(12)),
+            ),
+            Err(VerifyError::PublicInputs(_))
+        ));
+
+        let committed = IoVisibility::committed_inputs(&["a"], Fr::from(11));
+        let inputs = vec![true; ckt.inputs.len()];
+        assert!(
+            prove_partitioned(&params, &pk, &partition, &inputs, &committed, &binding).is_err()
+        );
+    }
+
+    #[test]
+    fn ipa_proofs_verify_without_trusted_setup() {
+        let inst = cla_adder();
+        let visibility = IoVisibility::committed_inputs(&["a"], Fp::from(3));
+        let binding = NetlistBinding::Mimc7Cbc(EncryptionKey::random());
+
+        let size = CircuitSize::new(&inst.ckt, &visibility, &binding);
+        let params = IpaParams::generate(size.k);
+        let shape = CircuitShape::new(&inst.ckt, &visibility, &binding, size).unwrap();
+        let pk = keygen(&params, &shape).unwrap();
+        let (proof, public_inputs) = prove(&params, &pk, inst, &visibility, &binding).unwrap();
+        assert!(verify(&params, &pk.verifying_key(), &public_inputs, &proof).is_ok());
+
+        let mut params_bytes = vec![];
+        write_params(&params, &mut params_bytes).unwrap();
+        let params = read_params::<IpaParams>(&mut &params_bytes[..]).unwrap();
+        assert!(verify(&params, &pk.verifying_key(), &public_inputs, &proof).is_ok());
+
+        let mut public_inputs_bytes = vec![];
+        public_inputs.write(&mut public_inputs_bytes).unwrap();
+        let public_inputs = PublicInputs::<Fp>::read(&mut &public_inputs_bytes[..]).unwrap();
+        assert!(verify(&params, &pk.verifying_key(), &public_inputs, &proof).is_ok());
+
+        let options = ProofOptions {
+            transcript: TranscriptHash::Poseidon,
+            ..Default::default()
+        };
+        let (proof, public_inputs) =
+            prove_with_options(&params, &pk, cla_adder(), &visibility, &binding, &options).unwrap();
+        assert!(verify_with_options(
+            &params,
+            &pk.verifying_key(),
+            &public_inputs,
+            &proof,
+            &options
+        )
+        .is_ok());
+
+        let err = pk.verifying_key().write(&mut vec![]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+    }
+}
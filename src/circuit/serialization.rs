@@ -0,0 +1,550 @@
+//! Versioned binary format of parameters, keys, circuit shapes, proofs and public inputs.
+//!
+//! Every file starts with the magic bytes, the format version and the kind of its content.
+//! Integers are little-endian and field elements use their canonical 32-byte encoding.
+//! Contents which depend on the field, all but proofs, then name the modulus of the field.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Error as IoError, ErrorKind, Read, Write},
+};
+
+use super::field::ZktSimField;
+use super::gate_definition_table::NUM_GATE_TYPES;
+use super::prover::{CircuitShape, MultiOpen, ProofOptions, TranscriptHash};
+use super::{BatchPublicInputs, CircuitSize, NetlistBindingMode, NetlistCommitment, PublicInputs};
+
+const MAGIC: [u8; 6] = *b"zktsim";
+
+/// Bumped on every change to the format or to the circuit constraints after a release,
+/// which would make the stored keys stale.
+const FORMAT_VERSION: u16 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Kind {
+    Params = 1,
+    ProvingKey = 2,
+    VerifyingKey = 3,
+    Proof = 4,
+    PublicInputs = 5,
+    BatchPublicInputs = 6,
+    CircuitShape = 7,
+}
+
+fn invalid(msg: impl Into<String>) -> IoError {
+    IoError::new(ErrorKind::InvalidData, msg.into())
+}
+
+pub(super) fn write_header(writer: &mut impl Write, kind: Kind) -> Result<(), IoError> {
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    writer.write_all(&[kind as u8])
+}
+
+pub(super) fn read_header(reader: &mut impl Read, kind: Kind) -> Result<(), IoError> {
+    let mut magic = [0u8; 6];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(invalid("not a zktsim file"));
+    }
+
+    let mut version = [0u8; 2];
+    reader.read_exact(&mut version)?;
+    let version = u16::from_le_bytes(version);
+    if version != FORMAT_VERSION {
+        return Err(invalid(format!(
+            "format version {} is not supported, expected {}",
+            version, FORMAT_VERSION
+        )));
+    }
+
+    let found = read_u8(reader)?;
+    if found != kind as u8 {
+        return Err(invalid(format!(
+            "expected {:?}, found kind {}",
+            kind, found
+        )));
+    }
+
+    Ok(())
+}
+
+fn read_u8(reader: &mut impl Read) -> Result<u8, IoError> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn write_u64(writer: &mut impl Write, x: u64) -> Result<(), IoError> {
+    writer.write_all(&x.to_le_bytes())
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64, IoError> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_usize(reader: &mut impl Read) -> Result<usize, IoError> {
+    usize::try_from(read_u64(reader)?).map_err(|_| invalid("length overflows usize"))
+}
+
+fn write_field<F: ZktSimField>(writer: &mut impl Write, x: F) -> Result<(), IoError> {
+    writer.write_all(&x.to_repr())
+}
+
+fn read_field<F: ZktSimField>(reader: &mut impl Read) -> Result<F, IoError> {
+    let mut repr = [0u8; 32];
+    reader.read_exact(&mut repr)?;
+    Option::from(F::from_repr(repr)).ok_or_else(|| invalid("non-canonical field element"))
+}
+
+pub(super) fn write_modulus<F: ZktSimField>(writer: &mut impl Write) -> Result<(), IoError> {
+    write_bytes(writer, F::MODULUS.as_bytes())
+}
+
+/// Fails unless the content was written over the field `F`.
+pub(super) fn read_modulus<F: ZktSimField>(reader: &mut impl Read) -> Result<(), IoError> {
+    let modulus = read_bytes(reader)?;
+    if modulus != F::MODULUS.as_bytes() {
+        return Err(invalid(format!(
+            "content is over the field of modulus {}, expected {}",
+            String::from_utf8_lossy(&modulus),
+            F::MODULUS
+        )));
+    }
+    Ok(())
+}
+
+/// Length-prefixed byte string.
+pub(super) fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> Result<(), IoError> {
+    write_u64(writer, bytes.len() as u64)?;
+    writer.write_all(bytes)
+}
+
+pub(super) fn read_bytes(reader: &mut impl Read) -> Result<Vec<u8>, IoError> {
+    let len = read_u64(reader)?;
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+/// Writes the shape of a key, preceded by the number of gate types it was generated for.
+pub(super) fn write_shape(writer: &mut impl Write, shape: &CircuitShape) -> Result<(), IoError> {
+    write_u64(writer, NUM_GATE_TYPES as u64)?;
+
+    writer.write_all(&shape.size.k.to_le_bytes())?;
+    write_u64(writer, shape.size.num_gate_rows as u64)?;
+    write_u64(writer, shape.size.num_wire_rows as u64)?;
+    write_u64(writer, shape.size.batch_size as u64)?;
+
+    let binding = match shape.binding {
+        NetlistBindingMode::Mimc7Cbc => 0,
+        NetlistBindingMode::PoseidonSponge => 1,
+    };
+    writer.write_all(&[binding])?;
+
+    for wires in shape.committed_wires.iter() {
+        write_u64(writer, wires.len() as u64)?;
+        for &w in wires {
+            write_u64(writer, w)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(super) fn read_shape(reader: &mut impl Read) -> Result<CircuitShape, IoError> {
+    let num_gate_types = read_u64(reader)?;
+    if num_gate_types != NUM_GATE_TYPES as u64 {
+        return Err(invalid(format!(
+            "key was generated for {} gate types, expected {}",
+            num_gate_types, NUM_GATE_TYPES
+        )));
+    }
+
+    let mut k = [0u8; 4];
+    reader.read_exact(&mut k)?;
+    let size = CircuitSize {
+        k: u32::from_le_bytes(k),
+        num_gate_rows: read_usize(reader)?,
+        num_wire_rows: read_usize(reader)?,
+        batch_size: read_usize(reader)?,
+    };
+
+    let binding = match read_u8(reader)? {
+        0 => NetlistBindingMode::Mimc7Cbc,
+        1 => NetlistBindingMode::PoseidonSponge,
+        mode => return Err(invalid(format!("invalid netlist binding mode {}", mode))),
+    };
+
+    let mut read_wires = || -> Result<Vec<u64>, IoError> {
+        let len = read_u64(reader)?;
+        (0..len).map(|_| read_u64(reader)).collect()
+    };
+    let committed_wires = [read_wires()?, read_wires()?];
+
+    Ok(CircuitShape {
+        size,
+        binding,
+        committed_wires,
+    })
+}
+
+/// Writes the options of a proof, one byte each for the multi-opening argument and the
+/// transcript hash.
+pub(super) fn write_options(
+    writer: &mut impl Write,
+    options: &ProofOptions,
+) -> Result<(), IoError> {
+    let multiopen = match options.multiopen {
+        MultiOpen::Gwc => 0,
+        MultiOpen::Shplonk => 1,
+    };
+    let transcript = match options.transcript {
+        TranscriptHash::Blake2b => 0,
+        TranscriptHash::Keccak256 => 1,
+        TranscriptHash::Poseidon => 2,
+    };
+    writer.write_all(&[multiopen, transcript])
+}
+
+pub(super) fn read_options(reader: &mut impl Read) -> Result<ProofOptions, IoError> {
+    let multiopen = match read_u8(reader)? {
+        0 => MultiOpen::Gwc,
+        1 => MultiOpen::Shplonk,
+        multiopen => {
+            return Err(invalid(format!(
+                "invalid multi-opening argument {}",
+                multiopen
+            )))
+        }
+    };
+    let transcript = match read_u8(reader)? {
+        0 => TranscriptHash::Blake2b,
+        1 => TranscriptHash::Keccak256,
+        2 => TranscriptHash::Poseidon,
+        transcript => return Err(invalid(format!("invalid transcript hash {}", transcript))),
+    };
+
+    Ok(ProofOptions {
+        multiopen,
+        transcript,
+    })
+}
+
+impl<F: ZktSimField> PublicInputs<F> {
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), IoError> {
+        write_header(writer, Kind::PublicInputs)?;
+        write_modulus::<F>(writer)?;
+
+        write_u64(writer, self.num_gates as u64)?;
+
+        write_wire_values(writer, &self.public_wires)?;
+
+        for &commitment in self.wire_commitments.iter() {
+            write_field(writer, commitment)?;
+        }
+
+        write_netlist_commitment(writer, &self.netlist_commitment)
+    }
+
+    pub fn read(reader: &mut impl Read) -> Result<Self, IoError> {
+        read_header(reader, Kind::PublicInputs)?;
+        read_modulus::<F>(reader)?;
+
+        let num_gates = read_usize(reader)?;
+
+        let public_wires = read_wire_values(reader)?;
+        let wire_commitments = [read_field(reader)?, read_field(reader)?];
+        let netlist_commitment = read_netlist_commitment(reader)?;
+
+        Ok(Self {
+            num_gates,
+            public_wires,
+            wire_commitments,
+            netlist_commitment,
+        })
+    }
+
+    pub fn from_file(file_name: &str) -> Result<Self, IoError> {
+        read_file(file_name, |reader| Self::read(reader))
+    }
+
+    pub fn write_to_file(&self, file_name: &str) -> Result<(), IoError> {
+        write_file(file_name, |writer| self.write(writer))
+    }
+}
+
+impl<F: ZktSimField> BatchPublicInputs<F> {
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), IoError> {
+        write_header(writer, Kind::BatchPublicInputs)?;
+        write_modulus::<F>(writer)?;
+
+        write_u64(writer, self.num_gates as u64)?;
+
+        write_u64(writer, self.vectors.len() as u64)?;
+        for wires in self.vectors.iter() {
+            write_wire_values(writer, wires)?;
+        }
+
+        write_netlist_commitment(writer, &self.netlist_commitment)
+    }
+
+    pub fn read(reader: &mut impl Read) -> Result<Self, IoError> {
+        read_header(reader, Kind::BatchPublicInputs)?;
+        read_modulus::<F>(reader)?;
+
+        let num_gates = read_usize(reader)?;
+
+        let num_vectors = read_u64(reader)?;
+        let vectors = (0..num_vectors)
+            .map(|_| read_wire_values(reader))
+            .collect::<Result<Vec<_>, IoError>>()?;
+
+        let netlist_commitment = read_netlist_commitment(reader)?;
+
+        Ok(Self {
+            num_gates,
+            vectors,
+            netlist_commitment,
+        })
+    }
+
+    pub fn from_file(file_name: &str) -> Result<Self, IoError> {
+        read_file(file_name, |reader| Self::read(reader))
+    }
+
+    pub fn write_to_file(&self, file_name: &str) -> Result<(), IoError> {
+        write_file(file_name, |writer| self.write(writer))
+    }
+}
+
+/// Stored on its own, the shape lets a verifier read the verifying key without the netlist.
+impl CircuitShape {
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), IoError> {
+        write_header(writer, Kind::CircuitShape)?;
+        write_shape(writer, self)
+    }
+
+    pub fn read(reader: &mut impl Read) -> Result<Self, IoError> {
+        read_header(reader, Kind::CircuitShape)?;
+        read_shape(reader)
+    }
+
+    pub fn from_file(file_name: &str) -> Result<Self, IoError> {
+        read_file(file_name, |reader| Self::read(reader))
+    }
+
+    pub fn write_to_file(&self, file_name: &str) -> Result<(), IoError> {
+        write_file(file_name, |writer| self.write(writer))
+    }
+}
+
+fn write_wire_values(writer: &mut impl Write, wires: &[(u64, bool)]) -> Result<(), IoError> {
+    write_u64(writer, wires.len() as u64)?;
+    for &(w, val) in wires.iter() {
+        write_u64(writer, w)?;
+        writer.write_all(&[val as u8])?;
+    }
+    Ok(())
+}
+
+fn read_wire_values(reader: &mut impl Read) -> Result<Vec<(u64, bool)>, IoError> {
+    let num_wires = read_u64(reader)?;
+    (0..num_wires)
+        .map(|_| {
+            let w = read_u64(reader)?;
+            let val = match read_u8(reader)? {
+                0 => false,
+                1 => true,
+                val => return Err(invalid(format!("invalid wire value {}", val))),
+            };
+            Ok((w, val))
+        })
+        .collect()
+}
+
+fn write_netlist_commitment<F: ZktSimField>(
+    writer: &mut impl Write,
+    netlist_commitment: &NetlistCommitment<F>,
+) -> Result<(), IoError> {
+    match netlist_commitment {
+        NetlistCommitment::Mimc7Cbc {
+            key_hash,
+            ciphertext,
+        } => {
+            writer.write_all(&[0])?;
+            write_field(writer, *key_hash)?;
+            write_u64(writer, ciphertext.len() as u64)?;
+            for &block in ciphertext.iter() {
+                write_field(writer, block)?;
+            }
+        }
+        NetlistCommitment::PoseidonSponge {
+            digest,
+            num_gate_rows,
+        } => {
+            writer.write_all(&[1])?;
+            write_field(writer, *digest)?;
+            write_u64(writer, *num_gate_rows as u64)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn read_netlist_commitment<F: ZktSimField>(
+    reader: &mut impl Read,
+) -> Result<NetlistCommitment<F>, IoError> {
+    match read_u8(reader)? {
+        0 => {
+            let key_hash = read_field(reader)?;
+            let num_blocks = read_u64(reader)?;
+            let ciphertext = (0..num_blocks)
+                .map(|_| read_field(reader))
+                .collect::<Result<Vec<_>, IoError>>()?;
+            Ok(NetlistCommitment::Mimc7Cbc {
+                key_hash,
+                ciphertext,
+            })
+        }
+        1 => Ok(NetlistCommitment::PoseidonSponge {
+            digest: read_field(reader)?,
+            num_gate_rows: read_usize(reader)?,
+        }),
+        mode => Err(invalid(format!("invalid netlist commitment mode {}", mode))),
+    }
+}
+
+pub(super) fn read_file<T>(
+    file_name: &str,
+    read: impl FnOnce(&mut BufReader<File>) -> Result<T, IoError>,
+) -> Result<T, IoError> {
+    let mut reader = BufReader::new(File::open(file_name)?);
+    read(&mut reader)
+}
+
+pub(super) fn write_file(
+    file_name: &str,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<(), IoError>,
+) -> Result<(), IoError> {
+    let mut writer = BufWriter::new(File::create(file_name)?);
+    write(&mut writer)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2curves::{bn256::Fr as F, pasta::pallas::Base as Fp};
+
+    use super::*;
+
+    fn public_inputs() -> PublicInputs {
+        PublicInputs {
+            num_gates: 5,
+            public_wires: vec![(0, true), (3, false), (7, true)],
+            wire_commitments: [F::from(11), F::zero()],
+            netlist_commitment: NetlistCommitment::Mimc7Cbc {
+                key_hash: F::from(13),
+                ciphertext: vec![F::from(17), -F::one()],
+            },
+        }
+    }
+
+    #[test]
+    fn public_inputs_round_trip() {
+        let sponge = PublicInputs {
+            netlist_commitment: NetlistCommitment::PoseidonSponge {
+                digest: F::from(19),
+                num_gate_rows: 6,
+            },
+            ..public_inputs()
+        };
+
+        for public_inputs in [public_inputs(), sponge] {
+            let mut bytes = vec![];
+            public_inputs.write(&mut bytes).unwrap();
+            assert_eq!(PublicInputs::read(&mut &bytes[..]).unwrap(), public_inputs);
+        }
+    }
+
+    #[test]
+    fn batch_public_inputs_round_trip() {
+        let public_inputs = public_inputs();
+        let batch = BatchPublicInputs {
+            num_gates: public_inputs.num_gates,
+            vectors: vec![public_inputs.public_wires.clone(), vec![(3, true)]],
+            netlist_commitment: public_inputs.netlist_commitment.clone(),
+        };
+
+        let mut bytes = vec![];
+        batch.write(&mut bytes).unwrap();
+        assert_eq!(
+            BatchPublicInputs::<F>::read(&mut &bytes[..]).unwrap(),
+            batch
+        );
+        assert!(PublicInputs::<F>::read(&mut &bytes[..]).is_err());
+    }
+
+    #[test]
+    fn fields_are_checked() {
+        let mut bytes = vec![];
+        public_inputs().write(&mut bytes).unwrap();
+
+        assert!(PublicInputs::<Fp>::read(&mut &bytes[..]).is_err());
+    }
+
+    #[test]
+    fn shape_round_trips() {
+        let shape = CircuitShape {
+            size: CircuitSize {
+                k: 10,
+                num_gate_rows: 30,
+                num_wire_rows: 40,
+                batch_size: 2,
+            },
+            binding: NetlistBindingMode::PoseidonSponge,
+            committed_wires: [vec![1, 2], vec![39]],
+        };
+
+        let mut bytes = vec![];
+        write_shape(&mut bytes, &shape).unwrap();
+        assert_eq!(read_shape(&mut &bytes[..]).unwrap(), shape);
+
+        // Gate type count
+        bytes[0] += 1;
+        assert!(read_shape(&mut &bytes[..]).is_err());
+
+        let mut file_bytes = vec![];
+        shape.write(&mut file_bytes).unwrap();
+        assert_eq!(CircuitShape::read(&mut &file_bytes[..]).unwrap(), shape);
+        assert!(PublicInputs::<F>::read(&mut &file_bytes[..]).is_err());
+    }
+
+    #[test]
+    fn headers_are_checked() {
+        let mut bytes = vec![];
+        public_inputs().write(&mut bytes).unwrap();
+
+        let mut wrong_kind = bytes.clone();
+        wrong_kind[MAGIC.len() + 2] = Kind::Proof as u8;
+        let mut wrong_version = bytes.clone();
+        wrong_version[MAGIC.len()] += 1;
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] ^= 1;
+        let truncated = &bytes[..bytes.len() - 1];
+
+        for bytes in [
+            &wrong_kind[..],
+            &wrong_version[..],
+            &wrong_magic[..],
+            truncated,
+        ] {
+            assert!(PublicInputs::<F>::read(&mut &bytes[..]).is_err());
+        }
+    }
+}
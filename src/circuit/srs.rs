@@ -0,0 +1,262 @@
+//! Loading KZG parameters from the outcome of a public setup ceremony.
+//!
+//! Proofs are only sound for a verifier who trusts that nobody knows the secret of the
+//! parameters, so shared proofs must use the parameters of a multi-party ceremony rather
+//! than [`setup`](super::prover::setup).
+
+use std::{
+    fs::File,
+    io::{BufReader, Error as IoError, ErrorKind, Read, Seek, SeekFrom},
+};
+
+use ff::PrimeField;
+use halo2_proofs::{arithmetic::g_to_lagrange, poly::commitment::Params as _, SerdeFormat};
+use halo2curves::{
+    bn256::{Fq, G1Affine, G2Affine, G1},
+    serde::SerdeObject,
+};
+
+use super::prover::Params;
+
+const PTAU_MAGIC: [u8; 4] = *b"ptau";
+
+// Sections of a ptau file
+const PTAU_HEADER: u32 = 1;
+const PTAU_TAU_G1: u32 = 2;
+const PTAU_TAU_G2: u32 = 3;
+
+fn invalid(msg: impl Into<String>) -> IoError {
+    IoError::new(ErrorKind::InvalidData, msg.into())
+}
+
+/// Reads halo2 KZG parameters, such as the conversions of the Perpetual Powers of Tau,
+/// and downsizes them to `2^k` rows.
+pub fn params_from_srs_file(file_name: &str, k: u32) -> Result<Params, IoError> {
+    let mut reader = BufReader::new(File::open(file_name)?);
+    let mut params = Params::read_custom(&mut reader, SerdeFormat::RawBytes)?;
+
+    if params.k() < k {
+        return Err(invalid(format!(
+            "parameters support up to k = {}, the circuit needs k = {}",
+            params.k(),
+            k
+        )));
+    }
+    if params.k() > k {
+        params.downsize(k);
+    }
+
+    Ok(params)
+}
+
+/// Reads the powers of tau of a snarkjs ptau file over BN254, such as those of the
+/// Perpetual Powers of Tau or Hermez ceremonies, and keeps the first `2^k` of them.
+pub fn params_from_ptau_file(file_name: &str, k: u32) -> Result<Params, IoError> {
+    read_ptau(&mut BufReader::new(File::open(file_name)?), k)
+}
+
+pub(super) fn read_ptau(reader: &mut (impl Read + Seek), k: u32) -> Result<Params, IoError> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != PTAU_MAGIC {
+        return Err(invalid("not a ptau file"));
+    }
+    let _version = read_u32(reader)?;
+
+    // Offsets of the sections, which may come in any order
+    let num_sections = read_u32(reader)?;
+    let mut sections = vec![];
+    for _ in 0..num_sections {
+        let section = read_u32(reader)?;
+        let size = read_u64(reader)?;
+        sections.push((section, reader.stream_position()?));
+        reader.seek(SeekFrom::Current(size as i64))?;
+    }
+    let section_offset = |section: u32| -> Result<SeekFrom, IoError> {
+        sections
+            .iter()
+            .find(|&&(s, _)| s == section)
+            .map(|&(_, offset)| SeekFrom::Start(offset))
+            .ok_or_else(|| invalid(format!("ptau file has no section {}", section)))
+    };
+
+    reader.seek(section_offset(PTAU_HEADER)?)?;
+    let n8 = read_u32(reader)?;
+    if n8 != 32 {
+        return Err(invalid("ptau file is not over BN254"));
+    }
+    let mut q = [0u8; 32];
+    reader.read_exact(&mut q)?;
+    let q_hex = q
+        .iter()
+        .rev()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    if Fq::MODULUS.trim_start_matches("0x") != q_hex {
+        return Err(invalid("ptau file is not over BN254"));
+    }
+    let power = read_u32(reader)?;
+    if power < k {
+        return Err(invalid(format!(
+            "ptau file supports up to k = {}, the circuit needs k = {}",
+            power, k
+        )));
+    }
+
+    // Powers of tau in G1, in Montgomery form like the raw encoding of halo2curves
+    reader.seek(section_offset(PTAU_TAU_G1)?)?;
+    let g = (0..1u64 << k)
+        .map(|_| G1Affine::read_raw(reader))
+        .collect::<Result<Vec<_>, IoError>>()?;
+    let g_lagrange = g_to_lagrange(g.iter().map(|&p| G1::from(p)).collect(), k);
+
+    reader.seek(section_offset(PTAU_TAU_G2)?)?;
+    let g2 = G2Affine::read_raw(reader)?;
+    let s_g2 = G2Affine::read_raw(reader)?;
+
+    // Same layout as `ParamsKZG::write_custom`, which checks the points once more on reading
+    let mut bytes = k.to_le_bytes().to_vec();
+    for p in g.iter().chain(g_lagrange.iter()) {
+        p.write_raw(&mut bytes)?;
+    }
+    g2.write_raw(&mut bytes)?;
+    s_g2.write_raw(&mut bytes)?;
+
+    Params::read_custom(&mut &bytes[..], SerdeFormat::RawBytes)
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32, IoError> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64, IoError> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, path::PathBuf};
+
+    use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+    use halo2curves::bn256::{Bn256, Fr, G2};
+
+    use super::*;
+
+    fn params_bytes(params: &Params) -> Vec<u8> {
+        let mut bytes = vec![];
+        params
+            .write_custom(&mut bytes, SerdeFormat::RawBytes)
+            .unwrap();
+        bytes
+    }
+
+    /// ptau file with the powers of `tau` up to `2^power`, laid out like snarkjs.
+    fn ptau(tau: Fr, power: u32) -> Vec<u8> {
+        let powers = |n: u64| {
+            std::iter::successors(Some(Fr::one()), move |x| Some(x * tau)).take(n as usize)
+        };
+
+        // The modulus, little-endian: q - 1 plus one, without carry
+        let mut q = (-Fq::one()).to_repr().as_ref().to_vec();
+        q[0] += 1;
+
+        let mut header = 32u32.to_le_bytes().to_vec();
+        header.extend(q);
+        header.extend(power.to_le_bytes());
+        header.extend(power.to_le_bytes());
+
+        let mut tau_g1 = vec![];
+        for x in powers((1 << power) * 2 - 1) {
+            G1Affine::from(G1::generator() * x)
+                .write_raw(&mut tau_g1)
+                .unwrap();
+        }
+        let mut tau_g2 = vec![];
+        for x in powers(1 << power) {
+            G2Affine::from(G2::generator() * x)
+                .write_raw(&mut tau_g2)
+                .unwrap();
+        }
+
+        let mut bytes = PTAU_MAGIC.to_vec();
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend(3u32.to_le_bytes());
+        // Sections out of order
+        for (section, data) in [
+            (PTAU_TAU_G2, tau_g2),
+            (PTAU_HEADER, header),
+            (PTAU_TAU_G1, tau_g1),
+        ] {
+            bytes.extend(section.to_le_bytes());
+            bytes.extend((data.len() as u64).to_le_bytes());
+            bytes.extend(data);
+        }
+        bytes
+    }
+
+    /// File in the temporary directory, removed on drop.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(extension: &str) -> Self {
+            Self(std::env::temp_dir().join(format!(
+                "zktsim_{}_{:016x}.{}",
+                std::process::id(),
+                rand::random::<u64>(),
+                extension
+            )))
+        }
+
+        fn name(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn ptau_powers_match_the_setup_with_the_same_secret() {
+        let tau = Fr::from(1234567);
+        let expected = ParamsKZG::<Bn256>::unsafe_setup_with_s(3, tau);
+
+        let params = read_ptau(&mut Cursor::new(ptau(tau, 4)), 3).unwrap();
+        assert_eq!(params_bytes(&params), params_bytes(&expected));
+
+        assert!(read_ptau(&mut Cursor::new(ptau(tau, 2)), 3).is_err());
+    }
+
+    #[test]
+    fn ptau_fixture_is_read() {
+        // Written by examples/pot_2.py with tau = 1234567, with every section of snarkjs
+        for k in [1, 2] {
+            let expected = ParamsKZG::<Bn256>::unsafe_setup_with_s(k, Fr::from(1234567));
+            let params = params_from_ptau_file("examples/pot_2.ptau", k).unwrap();
+            assert_eq!(params_bytes(&params), params_bytes(&expected), "k = {}", k);
+        }
+        assert!(params_from_ptau_file("examples/pot_2.ptau", 3).is_err());
+    }
+
+    #[test]
+    fn srs_files_are_downsized() {
+        let tau = Fr::from(7654321);
+        let file = TempFile::new("srs");
+        let file_name = file.name();
+
+        let large = ParamsKZG::<Bn256>::unsafe_setup_with_s(4, tau);
+        std::fs::write(file_name, params_bytes(&large)).unwrap();
+
+        let params = params_from_srs_file(file_name, 3).unwrap();
+        let expected = ParamsKZG::<Bn256>::unsafe_setup_with_s(3, tau);
+        assert_eq!(params_bytes(&params), params_bytes(&expected));
+
+        assert!(params_from_srs_file(file_name, 5).is_err());
+    }
+}
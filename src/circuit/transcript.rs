@@ -0,0 +1,247 @@
+//! Poseidon proof transcript, whose challenges are cheap to recompute inside a circuit
+//! verifying the proof.
+//!
+//! Scalars are absorbed as they are, and each coordinate of a point as its low and high
+//! 128 bits, which fit in the scalar field; the identity absorbs as zero coordinates. A
+//! challenge is the Poseidon hash of the previous challenge followed by everything absorbed
+//! since.
+
+use std::{
+    io::{self, Read, Write},
+    marker::PhantomData,
+};
+
+use ff::{FromUniformBytes, PrimeField};
+use group::GroupEncoding;
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    transcript::{
+        Challenge255, EncodedChallenge, Transcript, TranscriptRead, TranscriptReadBuffer,
+        TranscriptWrite, TranscriptWriterBuffer,
+    },
+};
+use halo2curves::Coordinates;
+
+use super::field::ZktSimField;
+use super::native::poseidon_commit;
+
+#[derive(Clone, Debug)]
+struct PoseidonSponge<F> {
+    state: F,
+    absorbed: Vec<F>,
+}
+
+impl<F: ZktSimField> PoseidonSponge<F> {
+    fn new() -> Self {
+        Self {
+            state: F::ZERO,
+            absorbed: Vec::new(),
+        }
+    }
+
+    fn absorb_scalar(&mut self, scalar: F) {
+        self.absorbed.push(scalar);
+    }
+
+    fn absorb_point<C: CurveAffine>(&mut self, point: C) {
+        let coordinates: Option<Coordinates<C>> = point.coordinates().into();
+        let coordinates = coordinates
+            .map(|coordinates| [*coordinates.x(), *coordinates.y()])
+            .unwrap_or([C::Base::ZERO; 2]);
+
+        for coordinate in coordinates {
+            let repr = coordinate.to_repr();
+            for half in repr.as_ref().chunks(16) {
+                let mut bytes = [0u8; 16];
+                bytes[..half.len()].copy_from_slice(half);
+                self.absorbed.push(F::from_u128(u128::from_le_bytes(bytes)));
+            }
+        }
+    }
+
+    fn squeeze(&mut self) -> F {
+        let msg_vals = std::iter::once(self.state)
+            .chain(self.absorbed.drain(..))
+            .collect::<Vec<_>>();
+        self.state = poseidon_commit(&msg_vals);
+        self.state
+    }
+
+    /// The squeezed field element, as the input of a [`Challenge255`].
+    fn squeeze_challenge<C: CurveAffine>(&mut self) -> Challenge255<C>
+    where
+        C::Scalar: FromUniformBytes<64>,
+    {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.squeeze().to_repr());
+        Challenge255::new(&bytes)
+    }
+}
+
+/// Writer of a Poseidon transcript.
+#[derive(Debug)]
+pub struct PoseidonWrite<W: Write, C: CurveAffine> {
+    sponge: PoseidonSponge<C::Scalar>,
+    writer: W,
+    _marker: PhantomData<C>,
+}
+
+impl<W: Write, C: CurveAffine> TranscriptWriterBuffer<W, C, Challenge255<C>> for PoseidonWrite<W, C>
+where
+    C::Scalar: ZktSimField + FromUniformBytes<64>,
+{
+    fn init(writer: W) -> Self {
+        Self {
+            sponge: PoseidonSponge::new(),
+            writer,
+            _marker: PhantomData,
+        }
+    }
+
+    fn finalize(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write, C: CurveAffine> Transcript<C, Challenge255<C>> for PoseidonWrite<W, C>
+where
+    C::Scalar: ZktSimField + FromUniformBytes<64>,
+{
+    fn squeeze_challenge(&mut self) -> Challenge255<C> {
+        self.sponge.squeeze_challenge()
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        self.sponge.absorb_point(point);
+        Ok(())
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.sponge.absorb_scalar(scalar);
+        Ok(())
+    }
+}
+
+impl<W: Write, C: CurveAffine> TranscriptWrite<C, Challenge255<C>> for PoseidonWrite<W, C>
+where
+    C::Scalar: ZktSimField + FromUniformBytes<64>,
+{
+    fn write_point(&mut self, point: C) -> io::Result<()> {
+        self.common_point(point)?;
+        self.writer.write_all(point.to_bytes().as_ref())
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.common_scalar(scalar)?;
+        self.writer.write_all(scalar.to_repr().as_ref())
+    }
+}
+
+/// Reader of a Poseidon transcript.
+#[derive(Debug)]
+pub struct PoseidonRead<R: Read, C: CurveAffine> {
+    sponge: PoseidonSponge<C::Scalar>,
+    reader: R,
+    _marker: PhantomData<C>,
+}
+
+impl<R: Read, C: CurveAffine> TranscriptReadBuffer<R, C, Challenge255<C>> for PoseidonRead<R, C>
+where
+    C::Scalar: ZktSimField + FromUniformBytes<64>,
+{
+    fn init(reader: R) -> Self {
+        Self {
+            sponge: PoseidonSponge::new(),
+            reader,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<R: Read, C: CurveAffine> Transcript<C, Challenge255<C>> for PoseidonRead<R, C>
+where
+    C::Scalar: ZktSimField + FromUniformBytes<64>,
+{
+    fn squeeze_challenge(&mut self) -> Challenge255<C> {
+        self.sponge.squeeze_challenge()
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        self.sponge.absorb_point(point);
+        Ok(())
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.sponge.absorb_scalar(scalar);
+        Ok(())
+    }
+}
+
+impl<R: Read, C: CurveAffine> TranscriptRead<C, Challenge255<C>> for PoseidonRead<R, C>
+where
+    C::Scalar: ZktSimField + FromUniformBytes<64>,
+{
+    fn read_point(&mut self) -> io::Result<C> {
+        let mut compressed = C::Repr::default();
+        self.reader.read_exact(compressed.as_mut())?;
+        let point: C = Option::from(C::from_bytes(&compressed)).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid point encoding in proof",
+            )
+        })?;
+        self.common_point(point)?;
+
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        let mut repr = <C::Scalar as PrimeField>::Repr::default();
+        self.reader.read_exact(repr.as_mut())?;
+        let scalar: C::Scalar = Option::from(C::Scalar::from_repr(repr)).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid field element encoding in proof",
+            )
+        })?;
+        self.common_scalar(scalar)?;
+
+        Ok(scalar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2curves::bn256::{Fr, G1Affine};
+    use halo2curves::group::{prime::PrimeCurveAffine, Curve};
+
+    use super::*;
+
+    #[test]
+    fn reader_replays_the_writer_challenges() {
+        let point = (G1Affine::generator() * Fr::from(5)).to_affine();
+
+        let mut writer = PoseidonWrite::<_, G1Affine>::init(vec![]);
+        writer.write_point(point).unwrap();
+        writer.write_scalar(Fr::from(7)).unwrap();
+        let first = writer.squeeze_challenge().get_scalar();
+        writer.write_point(G1Affine::identity()).unwrap();
+        let second = writer.squeeze_challenge().get_scalar();
+        assert_ne!(first, second);
+        let proof = writer.finalize();
+
+        let mut reader = PoseidonRead::<_, G1Affine>::init(&proof[..]);
+        assert_eq!(reader.read_point().unwrap(), point);
+        assert_eq!(reader.read_scalar().unwrap(), Fr::from(7));
+        assert_eq!(reader.squeeze_challenge().get_scalar(), first);
+        assert_eq!(reader.read_point().unwrap(), G1Affine::identity());
+        assert_eq!(reader.squeeze_challenge().get_scalar(), second);
+
+        // Anything absorbed besides the proof changes the challenges
+        let mut reader = PoseidonRead::<_, G1Affine>::init(&proof[..]);
+        reader.common_scalar(Fr::one()).unwrap();
+        reader.read_point().unwrap();
+        reader.read_scalar().unwrap();
+        assert_ne!(reader.squeeze_challenge().get_scalar(), first);
+    }
+}
@@ -0,0 +1,167 @@
+use std::{
+    collections::HashMap,
+    io::{Error as IoError, ErrorKind},
+};
+
+use halo2curves::bn256::Fr;
+
+use crate::boolean_circuit::BooleanCircuit;
+
+use super::field::ZktSimField;
+
+/// How the value of a primary input or output wire is revealed to the verifier.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Visibility {
+    /// Exposed in the expected input/output instance columns
+    #[default]
+    Public,
+    /// Only constrained by the gate and wire-assignment lookups
+    Private,
+    /// Hidden like private wires, but bound by a public Poseidon commitment
+    Committed,
+}
+
+/// Per-port visibility of the circuit inputs and outputs.
+///
+/// Entries are keyed either by port name (`a`), covering every bit of the port, or by bit
+/// name (`a[3]`), which takes precedence. Ports without an entry are public.
+///
+/// Committed inputs and committed outputs are each hashed as
+/// `Poseidon(commitment_salt, wire values...)` in port order, see [`wire_commitment`].
+///
+/// [`wire_commitment`]: super::wire_commitment
+#[derive(Clone, Debug, Default)]
+pub struct IoVisibility<F = Fr> {
+    pub inputs: HashMap<String, Visibility>,
+    pub outputs: HashMap<String, Visibility>,
+    /// Blinding value absorbed ahead of the committed wire values. Keep it secret and
+    /// reuse it to reproduce the same commitment across proofs; with a known salt, inputs
+    /// of low entropy can be brute-forced from their commitment.
+    pub commitment_salt: F,
+}
+
+impl<F: ZktSimField> IoVisibility<F> {
+    pub fn all_public() -> Self {
+        Self::default()
+    }
+
+    /// Marks the given input ports as private.
+    pub fn private_inputs(ports: &[&str]) -> Self {
+        Self {
+            inputs: ports
+                .iter()
+                .map(|port| (port.to_string(), Visibility::Private))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Marks the given input ports as committed under the given salt.
+    pub fn committed_inputs(ports: &[&str], commitment_salt: F) -> Self {
+        Self {
+            inputs: ports
+                .iter()
+                .map(|port| (port.to_string(), Visibility::Committed))
+                .collect(),
+            commitment_salt,
+            ..Default::default()
+        }
+    }
+
+    /// Visibility of each wire in `ckt.inputs`, in order.
+    pub fn of_inputs(&self, ckt: &BooleanCircuit) -> Vec<Visibility> {
+        Self::resolve(&self.inputs, &ckt.input_names, ckt.inputs.len())
+    }
+
+    /// Visibility of each wire in `ckt.outputs`, in order.
+    pub fn of_outputs(&self, ckt: &BooleanCircuit) -> Vec<Visibility> {
+        Self::resolve(&self.outputs, &ckt.output_names, ckt.outputs.len())
+    }
+
+    /// Committed input wires and committed output wires, in port order.
+    pub fn committed_wires(&self, ckt: &BooleanCircuit) -> [Vec<u64>; 2] {
+        let committed = |wires: &[u64], vis: Vec<Visibility>| {
+            wires
+                .iter()
+                .zip(vis)
+                .filter(|(_, vis)| *vis == Visibility::Committed)
+                .map(|(&w, _)| w)
+                .collect::<Vec<_>>()
+        };
+
+        [
+            committed(&ckt.inputs, self.of_inputs(ckt)),
+            committed(&ckt.outputs, self.of_outputs(ckt)),
+        ]
+    }
+
+    /// Fails if an entry names neither a port nor a port bit of the circuit, as the port
+    /// it was meant for would be left public.
+    pub fn check(&self, ckt: &BooleanCircuit) -> Result<(), IoError> {
+        Self::check_names(&self.inputs, &ckt.input_names, "input")?;
+        Self::check_names(&self.outputs, &ckt.output_names, "output")
+    }
+
+    pub(super) fn check_names(
+        ports: &HashMap<String, Visibility>,
+        names: &[String],
+        direction: &str,
+    ) -> Result<(), IoError> {
+        let port = |name: &str| name.split('[').next().unwrap_or("").to_string();
+
+        if let Some(key) = ports
+            .keys()
+            .find(|key| !names.iter().any(|name| name == *key || port(name) == **key))
+        {
+            return Err(IoError::new(
+                ErrorKind::InvalidInput,
+                format!("the circuit has no {} port or bit named {}", direction, key),
+            ));
+        }
+        Ok(())
+    }
+
+    fn resolve(
+        ports: &HashMap<String, Visibility>,
+        names: &[String],
+        len: usize,
+    ) -> Vec<Visibility> {
+        (0..len)
+            .map(|i| {
+                let name = names.get(i).map(String::as_str).unwrap_or("");
+                let port = name.split('[').next().unwrap_or("");
+                ports
+                    .get(name)
+                    .or_else(|| ports.get(port))
+                    .copied()
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_ports_are_rejected() {
+        let ckt = BooleanCircuit::from_netlist("examples/cla_adder_6b.zkt").unwrap();
+
+        let known = IoVisibility::<Fr>::private_inputs(&["a", "b[3]"]);
+        assert!(known.check(&ckt).is_ok());
+
+        for ports in [["secert"], ["a[6]"], ["sum"]] {
+            let unknown = IoVisibility::<Fr>::private_inputs(&ports);
+            assert!(unknown.check(&ckt).is_err(), "{:?}", ports);
+        }
+
+        let mut outputs = IoVisibility::<Fr>::all_public();
+        outputs
+            .outputs
+            .insert("sum".to_string(), Visibility::Private);
+        assert!(outputs.check(&ckt).is_ok());
+        outputs.outputs.insert("a".to_string(), Visibility::Private);
+        assert!(outputs.check(&ckt).is_err());
+    }
+}
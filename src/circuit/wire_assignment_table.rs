@@ -10,8 +10,10 @@ use halo2curves::ff::PrimeField;
 use super::common::*;
 
 #[derive(Debug, Clone)]
-pub(super) struct WireAssignmentTableConfig<F: PrimeField, const W: usize> {
+pub(super) struct WireAssignmentTableConfig<F: PrimeField> {
     pub(super) internal_enable_wire: Column<Fixed>,
+    /// Input vector of the batch each row belongs to
+    pub(super) vector: Column<Fixed>,
     pub(super) idx: Column<Fixed>,
     pub(super) val: Column<Advice>,
 
@@ -22,41 +24,60 @@ pub(super) struct WireAssignmentTableAdvice {
     pub(super) val: Column<Advice>,
 }
 
-impl<F: PrimeField, const W: usize> WireAssignmentTableConfig<F, W> {
+impl<F: PrimeField> WireAssignmentTableConfig<F> {
     pub(super) fn configure(
         meta: &mut ConstraintSystem<F>,
         advice: WireAssignmentTableAdvice,
     ) -> Self {
         let internal_enable_wire = meta.fixed_column();
+        let vector = meta.fixed_column();
         let idx = meta.fixed_column();
 
+        // Committed wires are copied into the Poseidon commitment
+        meta.enable_equality(advice.val);
+
         Self {
             internal_enable_wire,
+            vector,
             idx,
             val: advice.val,
             _marker: PhantomData,
         }
     }
 
-    pub(super) fn load_fixed(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+    /// Loads `num_wire_rows` rows for each of the `batch_size` input vectors.
+    pub(super) fn load_fixed(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        num_wire_rows: usize,
+        batch_size: usize,
+    ) -> Result<(), Error> {
         layouter.assign_region(
             || "load wire-assignment table fixed part",
             |mut region| {
                 let mut offset = 0;
-                for value in 0..W {
-                    region.assign_fixed(
-                        || format!("i_e_w[{}]", offset),
-                        self.internal_enable_wire,
-                        offset,
-                        || Value::known(F::ONE),
-                    )?;
-                    region.assign_fixed(
-                        || format!("idx[{}]", offset),
-                        self.idx,
-                        offset,
-                        || Value::known(F::from(value as u64)),
-                    )?;
-                    offset += 1;
+                for vector in 0..batch_size {
+                    for value in 0..num_wire_rows {
+                        region.assign_fixed(
+                            || format!("i_e_w[{}]", offset),
+                            self.internal_enable_wire,
+                            offset,
+                            || Value::known(F::ONE),
+                        )?;
+                        region.assign_fixed(
+                            || format!("vector[{}]", offset),
+                            self.vector,
+                            offset,
+                            || Value::known(F::from(vector as u64)),
+                        )?;
+                        region.assign_fixed(
+                            || format!("idx[{}]", offset),
+                            self.idx,
+                            offset,
+                            || Value::known(F::from(value as u64)),
+                        )?;
+                        offset += 1;
+                    }
                 }
 
                 // For when internal_enable_wire is disabled
@@ -66,6 +87,12 @@ impl<F: PrimeField, const W: usize> WireAssignmentTableConfig<F, W> {
                     offset,
                     || Value::known(F::ZERO),
                 )?;
+                region.assign_fixed(
+                    || format!("vector[{}]", offset),
+                    self.vector,
+                    offset,
+                    || Value::known(F::ZERO),
+                )?;
                 region.assign_fixed(
                     || format!("idx[{}]", offset),
                     self.idx,
@@ -330,6 +330,32 @@ impl<F: PrimeField, const RATE: usize, const L: usize> Domain<F, RATE> for Const
     }
 }
 
+/// A Poseidon hash function used with variable input length.
+///
+/// The input is padded with a single one followed by zeroes up to a multiple of RATE,
+/// so that inputs of different lengths never share a padded message.
+#[derive(Clone, Copy, Debug)]
+pub struct VariableLength;
+
+impl<F: PrimeField, const RATE: usize> Domain<F, RATE> for VariableLength {
+    type Padding = iter::Chain<iter::Once<F>, iter::Take<iter::Repeat<F>>>;
+
+    fn name() -> String {
+        "VariableLength".to_string()
+    }
+
+    fn initial_capacity_element() -> F {
+        // ConstantLength capacity values are multiples of 2^64, so this cannot collide
+        // with any of them.
+        F::ONE
+    }
+
+    fn padding(input_len: usize) -> Self::Padding {
+        let k = (input_len + 1 + RATE - 1) / RATE;
+        iter::once(F::ONE).chain(iter::repeat(F::ZERO).take(k * RATE - input_len - 1))
+    }
+}
+
 /// A Poseidon hash function, built around a sponge.
 pub struct Hash<
     F: Field,
@@ -383,6 +409,22 @@ impl<F: PrimeField, S: Spec<F, T, RATE>, const T: usize, const RATE: usize, cons
     }
 }
 
+impl<F: PrimeField, S: Spec<F, T, RATE>, const T: usize, const RATE: usize>
+    Hash<F, S, VariableLength, T, RATE>
+{
+    /// Hashes the given input.
+    pub fn hash(mut self, message: &[F]) -> F {
+        for value in message
+            .iter()
+            .copied()
+            .chain(<VariableLength as Domain<F, RATE>>::padding(message.len()))
+        {
+            self.sponge.absorb(value);
+        }
+        self.sponge.finish_absorbing().squeeze()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{permute, ConstantLength, Hash, P128Pow5T3 as OrchardNullifier, Spec};
@@ -1,79 +1,706 @@
+use std::{
+    collections::HashMap, env, fmt, fs, io::Error as IoError, path::Path, process::ExitCode,
+};
+
+use halo2curves::bn256::Fr;
+
 use zktsim::boolean_circuit::{BooleanCircuit, BooleanCircuitInstance};
-use zktsim::circuit::{run_mock_prover, run_prover_kzg};
+use zktsim::circuit::prover::{
+    self, CircuitShape, Params, Proof, ProveError, ProvingKey, VerifyingKey,
+};
+use zktsim::circuit::{
+    CircuitSize, EncryptionKey, IoVisibility, NetlistBinding, NetlistBindingMode, PublicInputs,
+    Visibility,
+};
+
+const USAGE: &str = "\
+usage: zktsim <command> [options]
+
+commands:
+  simulate <netlist> --inputs <values> [--outputs <file>]
+      evaluates the netlist and prints its outputs, or writes them to the file
+  stats <netlist> [--binding <mode>] [visibility]
+      prints the gate counts of the netlist and the size of the circuit proving it
+  convert <netlist> --cnf <file> [--outputs <values>]
+      writes the Tseitin CNF of the netlist in DIMACS format, pinning the given outputs
+  keygen <netlist> --params <file> --pk <file> --vk <file> --shape <file>
+         [--ptau <file>] [--binding <mode>] [visibility]
+      generates the keys of the circuits of the shape of the netlist; the parameters are
+      converted from the ptau file into --params, which must not exist yet, else read
+      from --params if it exists, else generated from fresh randomness, which is only fit
+      for testing; larger parameters are downsized to the circuit, also by prove and verify
+  prove <netlist> --inputs <values> | --outputs <values>
+        --params <file> --pk <file> --shape <file>
+        --key <file> --proof <file> --public-inputs <file>
+        [--binding <mode>] [--commitment-salt <file>] [visibility]
+      proves the evaluation of the netlist on the inputs, or on inputs found by a SAT
+      solver to give the outputs, usually kept hidden with --private-inputs
+  verify --params <file> --vk <file> --shape <file> --proof <file> --public-inputs <file>
+         [--netlist <netlist> --key <file>]
+      verifies the proof, and that it was made for the netlist if given
+
+options:
+  --binding mimc|sponge          binding of the netlist: MiMC7 CBC encryption, the default,
+                                 or Poseidon sponge digest
+  --key <file>                   secret of the binding, the encryption key or the salt of
+                                 the digest; generated by prove if the file does not exist
+  --commitment-salt <file>       salt of the wire commitments, needed by prove with
+                                 committed ports; generated if the file does not exist
+  --private-inputs <ports>       comma-separated ports hidden from the verifier, and
+  --private-outputs <ports>
+  --committed-inputs <ports>     comma-separated ports only revealed through a commitment
+  --committed-outputs <ports>
 
-fn u64_to_bits_le(x: u64, n: usize) -> Vec<bool> {
-    let mut v = Vec::new();
-    for i in 0..n {
-        v.push((x >> i) & 1 == 1);
+values are either the bits of the ports in netlist order, such as 0110, or port=value
+pairs separated by commas or newlines, such as a=31,b=0x11,c_in=0b0, bit i of a value
+going to port[i]; @<file> reads the values from the file.
+
+exit status: 0 on success, 1 if a proof or a netlist commitment does not verify or no
+inputs give the outputs to prove, 2 on usage errors, 3 on I/O errors or invalid files, 4 if key generation or proving fails
+";
+
+const GATE_NAMES: [&str; 7] = ["not", "and", "nand", "or", "nor", "xor", "xnor"];
+
+#[derive(Debug)]
+enum CliError {
+    /// The command line is invalid
+    Usage(String),
+    /// A proof or a netlist commitment does not verify
+    Rejected(String),
+    /// A file cannot be read or written, or is invalid
+    Io(IoError),
+    /// The proving system failed to generate the keys or the proof
+    Prove(String),
+}
+
+impl CliError {
+    fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::Rejected(_) => ExitCode::from(1),
+            Self::Usage(_) => ExitCode::from(2),
+            Self::Io(_) => ExitCode::from(3),
+            Self::Prove(_) => ExitCode::from(4),
+        }
     }
-    v
 }
 
-fn bits_le_to_u64(v: &[bool]) -> u64 {
-    assert!(v.len() <= 64);
-    let mut x = 0;
-    for i in 0..v.len() {
-        if v[i] {
-            x |= 1 << i;
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usage(reason) => write!(f, "{}", reason),
+            Self::Rejected(reason) => write!(f, "rejected: {}", reason),
+            Self::Io(err) => write!(f, "{}", err),
+            Self::Prove(reason) => write!(f, "{}", reason),
         }
     }
-    x
 }
 
-#[allow(dead_code)]
-fn test_zktsim_cla() {
-    let ckt = BooleanCircuit::from_netlist("examples/cla_adder_6b.zkt").unwrap();
+impl From<IoError> for CliError {
+    fn from(err: IoError) -> Self {
+        Self::Io(err)
+    }
+}
 
-    let a = u64_to_bits_le(31, 6);
-    let b = u64_to_bits_le(17, 6);
-    let c_in = u64_to_bits_le(0, 1);
-    let inputs = vec![a, b, c_in].concat();
+impl From<ProveError> for CliError {
+    fn from(err: ProveError) -> Self {
+        match err {
+            ProveError::Io(err) => Self::Io(err),
+            ProveError::Plonk(_) => Self::Prove(err.to_string()),
+        }
+    }
+}
 
-    let inst = BooleanCircuitInstance::from_ckt_and_inputs(ckt, &inputs);
+fn usage(reason: impl Into<String>) -> CliError {
+    CliError::Usage(reason.into())
+}
+
+/// Arguments of a command: positional arguments, and options which all take a value.
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self, CliError> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    let value = args
+                        .next_if(|value| !value.starts_with("--"))
+                        .ok_or_else(|| usage(format!("option --{} needs a value", name)))?;
+                    if options.insert(name.to_string(), value).is_some() {
+                        return Err(usage(format!("option --{} is given twice", name)));
+                    }
+                }
+                None => positional.push(arg),
+            }
+        }
+
+        Ok(Self {
+            positional,
+            options,
+        })
+    }
+
+    fn optional(&mut self, name: &str) -> Option<String> {
+        self.options.remove(name)
+    }
+
+    fn required(&mut self, name: &str) -> Result<String, CliError> {
+        self.optional(name)
+            .ok_or_else(|| usage(format!("option --{} is required", name)))
+    }
+
+    /// The netlist given as the first positional argument.
+    fn netlist(&mut self) -> Result<BooleanCircuit, CliError> {
+        if self.positional.is_empty() {
+            return Err(usage("no netlist given"));
+        }
+        let file_name = self.positional.remove(0);
+        Ok(BooleanCircuit::from_netlist(&file_name)?)
+    }
+
+    fn binding_mode(&mut self) -> Result<NetlistBindingMode, CliError> {
+        match self.optional("binding").as_deref() {
+            None | Some("mimc") => Ok(NetlistBindingMode::Mimc7Cbc),
+            Some("sponge") => Ok(NetlistBindingMode::PoseidonSponge),
+            Some(mode) => Err(usage(format!("unknown netlist binding {}", mode))),
+        }
+    }
+
+    /// Visibility of the ports given by the visibility options, under the given salt.
+    ///
+    /// Fails on a port which `ckt` does not have, which would otherwise be left public.
+    fn visibility(
+        &mut self,
+        ckt: &BooleanCircuit,
+        commitment_salt: Fr,
+    ) -> Result<IoVisibility, CliError> {
+        let mut visibility = IoVisibility {
+            commitment_salt,
+            ..Default::default()
+        };
+
+        for (option, vis) in [
+            ("private-inputs", Visibility::Private),
+            ("committed-inputs", Visibility::Committed),
+        ] {
+            for port in self
+                .optional(option)
+                .iter()
+                .flat_map(|ports| ports.split(','))
+            {
+                visibility.inputs.insert(port.to_string(), vis);
+            }
+        }
+        for (option, vis) in [
+            ("private-outputs", Visibility::Private),
+            ("committed-outputs", Visibility::Committed),
+        ] {
+            for port in self
+                .optional(option)
+                .iter()
+                .flat_map(|ports| ports.split(','))
+            {
+                visibility.outputs.insert(port.to_string(), vis);
+            }
+        }
+
+        visibility
+            .check(ckt)
+            .map_err(|err| usage(err.to_string()))?;
+        Ok(visibility)
+    }
+
+    /// Fails on the arguments no command read.
+    fn finish(self) -> Result<(), CliError> {
+        if let Some(arg) = self.positional.first() {
+            return Err(usage(format!("unexpected argument {}", arg)));
+        }
+        if let Some(name) = self.options.keys().next() {
+            return Err(usage(format!("unknown option --{}", name)));
+        }
+        Ok(())
+    }
+}
+
+/// Reads a secret field element stored like an encryption key, or generates one and
+/// stores it if the file does not exist.
+fn secret(file_name: &str) -> Result<Fr, CliError> {
+    if !Path::new(file_name).exists() {
+        let key = EncryptionKey::<Fr>::random();
+        key.write_to_file(file_name)?;
+        eprintln!("zktsim: generated a fresh secret in {}", file_name);
+    }
+    Ok(EncryptionKey::<Fr>::from_file(file_name)?.to_field())
+}
+
+fn binding(mode: NetlistBindingMode, secret: Fr) -> NetlistBinding {
+    match mode {
+        NetlistBindingMode::Mimc7Cbc => NetlistBinding::Mimc7Cbc(EncryptionKey::from_field(secret)),
+        NetlistBindingMode::PoseidonSponge => NetlistBinding::PoseidonSponge { salt: secret },
+    }
+}
+
+/// Port name and bit index of a port bit named like `a[3]`.
+fn split_name(name: &str) -> (&str, usize) {
+    name.split_once('[')
+        .and_then(|(port, bit)| Some((port, bit.strip_suffix(']')?.parse().ok()?)))
+        .unwrap_or((name, 0))
+}
+
+/// Bits of a value, least significant first.
+fn parse_value(value: &str) -> Result<Vec<bool>, CliError> {
+    let invalid = || usage(format!("invalid value {}", value));
+
+    let digits = |digits: &str, bits_per_digit: usize| -> Result<Vec<bool>, CliError> {
+        let mut bits = Vec::new();
+        for c in digits.chars().rev() {
+            let digit = c.to_digit(1 << bits_per_digit).ok_or_else(invalid)?;
+            bits.extend((0..bits_per_digit).map(|i| (digit >> i) & 1 == 1));
+        }
+        Ok(bits)
+    };
+
+    if let Some(bin) = value.strip_prefix("0b") {
+        digits(bin, 1)
+    } else if let Some(hex) = value.strip_prefix("0x") {
+        digits(hex, 4)
+    } else {
+        let x: u64 = value.parse().map_err(|_| invalid())?;
+        Ok((0..64).map(|i| (x >> i) & 1 == 1).collect())
+    }
+}
+
+/// Values of the port bits named `names`, in order, from a bit string or `port=value`
+/// pairs, see [`USAGE`].
+fn parse_values(values: &str, names: &[String]) -> Result<Vec<bool>, CliError> {
+    let values = match values.strip_prefix('@') {
+        Some(file_name) => fs::read_to_string(file_name)?,
+        None => values.to_string(),
+    };
+
+    if !values.contains('=') {
+        let bits = values
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c {
+                '0' => Ok(false),
+                '1' => Ok(true),
+                _ => Err(usage(format!("invalid bit {}", c))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if bits.len() != names.len() {
+            return Err(usage(format!(
+                "{} bits given, the ports have {}",
+                bits.len(),
+                names.len()
+            )));
+        }
+        return Ok(bits);
+    }
+
+    let mut ports = HashMap::new();
+    for pair in values
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|pair| !pair.is_empty())
+    {
+        let (port, value) = pair
+            .split_once('=')
+            .ok_or_else(|| usage(format!("expected port=value, found {}", pair)))?;
+        ports.insert(port, parse_value(value)?);
+    }
+
+    let mut widths = HashMap::new();
+    let bits = names
+        .iter()
+        .map(|name| {
+            let (port, bit) = split_name(name);
+            let value = ports
+                .get(port)
+                .ok_or_else(|| usage(format!("no value given for port {}", port)))?;
+            let width = widths.entry(port).or_insert(0);
+            *width = (*width).max(bit + 1);
+            Ok(value.get(bit).copied().unwrap_or(false))
+        })
+        .collect::<Result<Vec<_>, CliError>>()?;
 
-    let outputs = inst
-        .ckt
+    for (port, value) in ports.iter() {
+        let Some(&width) = widths.get(port) else {
+            return Err(usage(format!("no port named {}", port)));
+        };
+        if value.iter().skip(width).any(|&bit| bit) {
+            return Err(usage(format!(
+                "value of port {} does not fit in {} bits",
+                port, width
+            )));
+        }
+    }
+
+    Ok(bits)
+}
+
+/// `port=value` lines of the port bits named `names`, in the order of the ports, with
+/// values in decimal, or in binary for ports wider than 64 bits.
+fn format_values(names: &[String], bits: &[bool]) -> String {
+    let mut ports: Vec<(&str, Vec<bool>)> = Vec::new();
+    for (name, &val) in names.iter().zip(bits.iter()) {
+        let (port, bit) = split_name(name);
+        let i = match ports.iter().position(|(p, _)| *p == port) {
+            Some(i) => i,
+            None => {
+                ports.push((port, Vec::new()));
+                ports.len() - 1
+            }
+        };
+        let value = &mut ports[i].1;
+        if value.len() <= bit {
+            value.resize(bit + 1, false);
+        }
+        value[bit] = val;
+    }
+
+    ports
+        .iter()
+        .map(|(port, value)| {
+            let value = if value.len() <= 64 {
+                let x = value
+                    .iter()
+                    .enumerate()
+                    .fold(0u64, |x, (i, &bit)| x | ((bit as u64) << i));
+                x.to_string()
+            } else {
+                let bits = value.iter().rev().map(|&bit| if bit { '1' } else { '0' });
+                format!("0b{}", bits.collect::<String>())
+            };
+            format!("{}={}\n", port, value)
+        })
+        .collect()
+}
+
+fn simulate(mut args: Args) -> Result<(), CliError> {
+    let ckt = args.netlist()?;
+    let inputs = parse_values(&args.required("inputs")?, &ckt.input_names)?;
+    let outputs_file = args.optional("outputs");
+    args.finish()?;
+
+    let assn = ckt.eval(&inputs);
+    let outputs = ckt
         .outputs
         .iter()
-        .map(|&i| inst.assn.wires[i as usize])
+        .map(|&w| assn.wires[w as usize])
         .collect::<Vec<_>>();
-    let s = bits_le_to_u64(&outputs[0..6]);
-    let c_out = bits_le_to_u64(&outputs[6..7]);
 
-    println!("s = {}", s);
-    println!("c_out = {}", c_out);
+    let outputs = format_values(&ckt.output_names, &outputs);
+    match outputs_file {
+        Some(file_name) => fs::write(file_name, outputs)?,
+        None => print!("{}", outputs),
+    }
+    Ok(())
+}
 
-    run_mock_prover(inst);
+fn stats(mut args: Args) -> Result<(), CliError> {
+    let ckt = args.netlist()?;
+    let mode = args.binding_mode()?;
+    let visibility = args.visibility(&ckt, Fr::zero())?;
+    args.finish()?;
 
-    println!("zktsim cla works!");
+    println!("inputs: {}", ckt.inputs.len());
+    println!("outputs: {}", ckt.outputs.len());
+    println!("wires: {}", ckt.max_wire_idx + 1);
+    println!("gates: {}", ckt.gates.len());
+    for (i, name) in GATE_NAMES.iter().enumerate() {
+        let count = ckt
+            .gates
+            .iter()
+            .filter(|gate_io| gate_io.gate == i as u64 + 1)
+            .count();
+        if count > 0 {
+            println!("  {}: {}", name, count);
+        }
+    }
+
+    let size = CircuitSize::new(&ckt, &visibility, &binding(mode, Fr::zero()));
+    println!("k: {}", size.k);
+    println!("gate rows: {}", size.num_gate_rows);
+    println!("wire rows: {}", size.num_wire_rows);
+    Ok(())
+}
+
+fn convert(mut args: Args) -> Result<(), CliError> {
+    let ckt = args.netlist()?;
+    let cnf_file = args.required("cnf")?;
+    let outputs = args
+        .optional("outputs")
+        .map(|values| parse_values(&values, &ckt.output_names))
+        .transpose()?;
+    args.finish()?;
+
+    let fixed = outputs
+        .map(|outputs| ckt.outputs.iter().copied().zip(outputs).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let cnf = ckt.to_cnf(&fixed)?;
+    cnf.write_dimacs(&cnf_file)?;
+
+    println!(
+        "{} variables and {} clauses written to {}",
+        cnf.num_vars,
+        cnf.clauses.len(),
+        cnf_file
+    );
+    Ok(())
+}
+
+fn keygen(mut args: Args) -> Result<(), CliError> {
+    let ckt = args.netlist()?;
+    let mode = args.binding_mode()?;
+    let visibility = args.visibility(&ckt, Fr::zero())?;
+    let ptau_file = args.optional("ptau");
+    let params_file = args.required("params")?;
+    let pk_file = args.required("pk")?;
+    let vk_file = args.required("vk")?;
+    let shape_file = args.required("shape")?;
+    args.finish()?;
+
+    // The shape does not depend on the secrets
+    let binding = binding(mode, Fr::zero());
+    let size = CircuitSize::new(&ckt, &visibility, &binding);
+    let shape = CircuitShape::new(&ckt, &visibility, &binding, size)?;
+
+    let params_exist = Path::new(&params_file).exists();
+    let params = if let Some(ptau_file) = ptau_file {
+        if params_exist {
+            return Err(usage(format!(
+                "--ptau would overwrite the parameters in {}",
+                params_file
+            )));
+        }
+        let params = prover::params_from_ptau_file(&ptau_file, size.k)?;
+        prover::write_params_to_file(&params, &params_file)?;
+        params
+    } else if params_exist {
+        prover::params_from_srs_file(&params_file, size.k)?
+    } else {
+        eprintln!(
+            "zktsim: generating parameters from fresh randomness, only fit for testing; \
+             see --ptau"
+        );
+        let params = prover::setup(size.k);
+        prover::write_params_to_file(&params, &params_file)?;
+        params
+    };
+
+    let pk = prover::keygen(&params, &shape)?;
+    pk.write_to_file(&pk_file)?;
+    pk.verifying_key().write_to_file(&vk_file)?;
+    shape.write_to_file(&shape_file)?;
+
+    println!("keys generated for k = {}", size.k);
+    Ok(())
 }
 
-fn test_zktsim_c6288() {
-    let ckt = BooleanCircuit::from_netlist("examples/c6288.zkt").unwrap();
+fn prove(mut args: Args) -> Result<(), CliError> {
+    let ckt = args.netlist()?;
+    let inputs = args
+        .optional("inputs")
+        .map(|values| parse_values(&values, &ckt.input_names))
+        .transpose()?;
+    let outputs = args
+        .optional("outputs")
+        .map(|values| parse_values(&values, &ckt.output_names))
+        .transpose()?;
+    let mode = args.binding_mode()?;
+    let key_file = args.required("key")?;
+    let commitment_salt = args.optional("commitment-salt");
+    let visibility = args.visibility(&ckt, Fr::zero())?;
+    let params_file = args.required("params")?;
+    let pk_file = args.required("pk")?;
+    let shape_file = args.required("shape")?;
+    let proof_file = args.required("proof")?;
+    let public_inputs_file = args.required("public-inputs")?;
+    args.finish()?;
 
-    let mut a = u64_to_bits_le(512, 16);
-    let mut b = u64_to_bits_le(2048, 16);
-    a.reverse();
-    b.reverse();
-    let inputs = vec![a, b].concat();
+    let inputs = match (inputs, outputs) {
+        (Some(inputs), None) => inputs,
+        (None, Some(outputs)) => ckt
+            .find_inputs(&outputs)?
+            .ok_or_else(|| CliError::Rejected("no inputs give these outputs".to_string()))?,
+        _ => return Err(usage("prove takes either --inputs or --outputs")),
+    };
+
+    let key = secret(&key_file)?;
+    let committed = visibility.committed_wires(&ckt);
+    let visibility = match commitment_salt {
+        Some(file_name) => IoVisibility {
+            commitment_salt: secret(&file_name)?,
+            ..visibility
+        },
+        None if committed.iter().any(|wires| !wires.is_empty()) => {
+            return Err(usage("committed ports need a --commitment-salt"));
+        }
+        None => visibility,
+    };
+
+    let shape = CircuitShape::from_file(&shape_file)?;
+    let params = prover::params_from_srs_file(&params_file, shape.size.k)?;
+    let pk = ProvingKey::<Params>::from_file(&pk_file, &shape)?;
 
     let inst = BooleanCircuitInstance::from_ckt_and_inputs(ckt, &inputs);
+    let (proof, public_inputs) =
+        prover::prove(&params, &pk, inst, &visibility, &binding(mode, key))?;
+    proof.write_to_file(&proof_file)?;
+    public_inputs.write_to_file(&public_inputs_file)?;
 
-    let mut outputs = inst
-        .ckt
-        .outputs
-        .iter()
-        .map(|&i| inst.assn.wires[i as usize])
-        .collect::<Vec<_>>();
-    outputs.reverse();
-    let p = bits_le_to_u64(&outputs);
-    println!("p = {}", p);
+    println!(
+        "proof of {} bytes written to {}",
+        proof.bytes.len(),
+        proof_file
+    );
+    Ok(())
+}
+
+fn verify(mut args: Args) -> Result<(), CliError> {
+    let params_file = args.required("params")?;
+    let vk_file = args.required("vk")?;
+    let shape_file = args.required("shape")?;
+    let proof_file = args.required("proof")?;
+    let public_inputs_file = args.required("public-inputs")?;
+    let netlist_file = args.optional("netlist");
+    let key_file = args.optional("key");
+    args.finish()?;
+
+    let netlist = match (netlist_file, key_file) {
+        (Some(netlist_file), Some(key_file)) => Some((netlist_file, key_file)),
+        (None, None) => None,
+        _ => return Err(usage("--netlist and --key go together")),
+    };
+
+    let shape = CircuitShape::from_file(&shape_file)?;
+    let params = prover::params_from_srs_file(&params_file, shape.size.k)?;
+    let vk = VerifyingKey::<Params>::from_file(&vk_file, &shape)?;
+    let proof = Proof::from_file(&proof_file)?;
+    let public_inputs = PublicInputs::<Fr>::from_file(&public_inputs_file)?;
 
-    run_prover_kzg(inst);
+    prover::verify(&params, &vk, &public_inputs, &proof)
+        .map_err(|err| CliError::Rejected(err.to_string()))?;
+    println!("proof verified");
 
-    println!("zktsim c6288 works!");
+    if let Some((netlist_file, key_file)) = netlist {
+        let ckt = BooleanCircuit::from_netlist(&netlist_file)?;
+        let key = EncryptionKey::<Fr>::from_file(&key_file)?.to_field();
+        if !public_inputs
+            .netlist_commitment
+            .is_commitment_to(&ckt, &binding(shape.binding, key))
+        {
+            return Err(CliError::Rejected(format!(
+                "the proof was not made for the netlist {}",
+                netlist_file
+            )));
+        }
+        println!("netlist commitment matches {}", netlist_file);
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let Some(command) = args.next() else {
+        eprint!("{}", USAGE);
+        return usage("").exit_code();
+    };
+
+    let result = Args::parse(args).and_then(|args| match command.as_str() {
+        "simulate" => simulate(args),
+        "stats" => stats(args),
+        "convert" => convert(args),
+        "keygen" => keygen(args),
+        "prove" => prove(args),
+        "verify" => verify(args),
+        "help" | "--help" | "-h" => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(usage(format!("unknown command {}", command))),
+    });
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("zktsim: {}", err);
+            if let CliError::Usage(_) = err {
+                eprintln!("run zktsim help for the usage");
+            }
+            err.exit_code()
+        }
+    }
 }
 
-fn main() {
-    test_zktsim_c6288();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn values_are_read_per_port() {
+        let names = names(&["a[0]", "a[1]", "a[2]", "b[0]", "c"]);
+
+        let bits = parse_values("a=6, b=0b1,c=0x0", &names).unwrap();
+        assert_eq!(bits, [false, true, true, true, false]);
+        assert_eq!(parse_values("01111", &names).unwrap(), bits);
+        assert_eq!(
+            parse_values(&format_values(&names, &bits), &names).unwrap(),
+            bits
+        );
+        assert_eq!(format_values(&names, &bits), "a=6\nb=1\nc=0\n");
+
+        assert!(parse_values("a=8,b=0,c=0", &names).is_err());
+        assert!(parse_values("a=1,b=0", &names).is_err());
+        assert!(parse_values("a=1,b=0,c=0,d=0", &names).is_err());
+        assert!(parse_values("0111", &names).is_err());
+        assert!(parse_values("a=0x1g,b=0,c=0", &names).is_err());
+    }
+
+    #[test]
+    fn options_take_a_value() {
+        let parse = |args: &[&str]| Args::parse(args.iter().map(|arg| arg.to_string()));
+
+        let mut args = parse(&["netlist.zkt", "--inputs", "0101"]).unwrap();
+        assert_eq!(args.required("inputs").unwrap(), "0101");
+        assert!(args.required("inputs").is_err());
+        assert_eq!(args.positional, ["netlist.zkt"]);
+
+        assert!(parse(&["--inputs"]).is_err());
+        assert!(parse(&["--inputs", "--outputs", "1"]).is_err());
+        assert!(parse(&["--inputs", "1", "--inputs", "0"]).is_err());
+        assert!(parse(&["--unknown", "1"]).unwrap().finish().is_err());
+    }
+
+    #[test]
+    fn misspelled_ports_are_usage_errors() {
+        let ckt = BooleanCircuit::from_netlist("examples/cla_adder_6b.zkt").unwrap();
+        let visibility = |args: &[&str]| {
+            Args::parse(args.iter().map(|arg| arg.to_string()))
+                .unwrap()
+                .visibility(&ckt, Fr::zero())
+        };
+
+        let known = visibility(&["--private-inputs", "a,b[0]", "--committed-outputs", "sum"]);
+        assert!(known.is_ok());
+
+        for args in [
+            ["--private-inputs", "a,secert"],
+            ["--committed-inputs", "b[6]"],
+            ["--private-outputs", "a"],
+        ] {
+            let err = visibility(&args).unwrap_err();
+            assert!(matches!(err, CliError::Usage(_)), "{:?}", args);
+        }
+    }
 }